@@ -5,11 +5,11 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
 use std::path::PathBuf;
-use vai_core::VaiContainer;
-use vai_decoder::FrameCompositor;
-use vai_encoder::{EncoderConfig, SceneAnalyzer, VideoReader};
+use vai_core::{LazyVaiContainer, VaiContainer};
+use vai_decoder::{compare_frames, FrameCompositor, LazyFrameCompositor};
+use vai_encoder::{extract_audio_track, AudioResampleOptions, EncoderConfig, SceneAnalyzer, VideoReader};
 
 #[derive(Parser)]
 #[command(name = "vai")]
@@ -46,6 +46,33 @@ enum Commands {
         /// Minimum region size in pixels
         #[arg(long, default_value = "64")]
         min_region: u32,
+
+        /// Maximum gap in pixels between motion regions before they are merged
+        #[arg(long, default_value = "8")]
+        merge_gap: u32,
+
+        /// Diff frames against the previously reconstructed frame instead of
+        /// the background, for smoother motion at the cost of decode-side state
+        #[arg(long)]
+        temporal_reference: bool,
+
+        /// Number of recent frame-to-frame SAD scores kept for the
+        /// mean/stddev cut threshold
+        #[arg(long, default_value = "20")]
+        scene_window: usize,
+
+        /// Multiplier (k) applied to the stddev of recent SAD scores, added
+        /// to their mean, to flag a scene cut
+        #[arg(long, default_value = "3.0")]
+        scene_k: f64,
+
+        /// Minimum number of frames a scene must span before another cut can be flagged
+        #[arg(long, default_value = "8")]
+        min_scene_len: usize,
+
+        /// Maximum number of frames a scene may span before a cut is forced
+        #[arg(long, default_value = "300")]
+        max_scene_len: usize,
     },
 
     /// Decode a VAI file to frames
@@ -64,6 +91,30 @@ enum Commands {
         /// Extract a single frame by frame number
         #[arg(long)]
         frame: Option<u64>,
+
+        /// Extract the embedded audio track (if present) to this sidecar file
+        #[arg(long)]
+        extract_audio: Option<PathBuf>,
+
+        /// When extracting frames to a directory, also write the audio
+        /// sidecar into that directory alongside them
+        #[arg(long)]
+        mux_audio: bool,
+    },
+
+    /// Compare a VAI file against its original source video and report
+    /// full-reference quality (PSNR/SSIM) to tune `--quality`/`--threshold`
+    Verify {
+        /// Input VAI file path
+        input: PathBuf,
+
+        /// Original source video the VAI file was encoded from
+        #[arg(long)]
+        source: PathBuf,
+
+        /// Optional path to write a per-frame CSV report to
+        #[arg(long)]
+        csv: Option<PathBuf>,
     },
 }
 
@@ -78,14 +129,37 @@ fn main() -> Result<()> {
             fps,
             threshold,
             min_region,
-        } => encode_video(input, output, quality, fps, threshold, min_region)?,
+            merge_gap,
+            temporal_reference,
+            scene_window,
+            scene_k,
+            min_scene_len,
+            max_scene_len,
+        } => encode_video(
+            input,
+            output,
+            quality,
+            fps,
+            threshold,
+            min_region,
+            merge_gap,
+            temporal_reference,
+            scene_window,
+            scene_k,
+            min_scene_len,
+            max_scene_len,
+        )?,
 
         Commands::Decode {
             input,
             output,
             info,
             frame,
-        } => decode_video(input, output, info, frame)?,
+            extract_audio,
+            mux_audio,
+        } => decode_video(input, output, info, frame, extract_audio, mux_audio)?,
+
+        Commands::Verify { input, source, csv } => verify_video(input, source, csv)?,
     }
 
     Ok(())
@@ -98,17 +172,20 @@ fn encode_video(
     fps: Option<f64>,
     threshold: u8,
     min_region: u32,
+    merge_gap: u32,
+    temporal_reference: bool,
+    scene_window: usize,
+    scene_k: f64,
+    min_scene_len: usize,
+    max_scene_len: usize,
 ) -> Result<()> {
     println!("Encoding video: {}", input.display());
     println!("Output: {}", output.display());
 
+    let input_path = input.to_str().context("Invalid input path")?;
+
     // Open video file
-    let mut reader = VideoReader::open(
-        input
-            .to_str()
-            .context("Invalid input path")?,
-    )
-    .context("Failed to open video file")?;
+    let mut reader = VideoReader::open(input_path).context("Failed to open video file")?;
 
     let width = reader.width();
     let height = reader.height();
@@ -120,18 +197,29 @@ fn encode_video(
         width, height, fps_num, fps_den, duration_ms
     );
 
-    // Analyze using streaming (processes one frame at a time)
-    println!("Analyzing scene and encoding (streaming)...");
     let config = EncoderConfig {
         quality,
         fps,
         threshold,
         min_region_size: min_region,
+        merge_gap,
+        temporal_reference,
+        use_ffmpeg_avif: false,
+        scene_window,
+        scene_adaptive_factor: scene_k,
+        min_scene_len_frames: min_scene_len,
+        max_scene_len,
     };
-
     let analyzer = SceneAnalyzer::new(config);
-    let container = analyzer
-        .analyze_streaming(&mut reader, width, height, fps_num, fps_den, duration_ms)
+
+    println!("Pass 1: detecting scene cuts...");
+    let segments = analyzer
+        .detect_cuts(&mut reader)
+        .context("Failed to detect scenes")?;
+    println!("Detected {} scene(s)", segments.len());
+
+    let mut container = analyzer
+        .analyze_parallel(input_path, segments, width, height, fps_num, fps_den, duration_ms)
         .context("Failed to analyze video")?;
 
     println!(
@@ -140,6 +228,20 @@ fn encode_video(
         container.timeline.len()
     );
 
+    // Reopen the source for audio: the reader above is already exhausted by
+    // scene detection, and analyze_parallel opens its own readers per
+    // segment the same way.
+    let mut audio_reader =
+        VideoReader::open(input_path).context("Failed to reopen video for audio extraction")?;
+    if let Some((audio_asset, audio_entry)) =
+        extract_audio_track(&mut audio_reader, AudioResampleOptions::default())
+            .context("Failed to extract audio track")?
+    {
+        println!("Extracted audio track ({} bytes)", audio_asset.data_size());
+        container.audio_assets.push(audio_asset);
+        container.audio_timeline.push(audio_entry);
+    }
+
     // Write VAI file
     println!("Writing VAI file...");
     let file = File::create(&output).context("Failed to create output file")?;
@@ -158,9 +260,40 @@ fn decode_video(
     output: Option<PathBuf>,
     info: bool,
     frame_num: Option<u64>,
+    extract_audio: Option<PathBuf>,
+    mux_audio: bool,
 ) -> Result<()> {
     println!("Decoding VAI file: {}", input.display());
 
+    // A single-frame extraction only ever touches the handful of assets
+    // active at that timestamp, so it goes through the lazy container
+    // instead of reading every asset in the file. `--info` still needs the
+    // full container (it reports the total compressed asset size).
+    if let (Some(frame_num), false) = (frame_num, info) {
+        let file = File::open(&input).context("Failed to open VAI file")?;
+        let lazy_container =
+            LazyVaiContainer::open(file).context("Failed to read VAI container")?;
+        let fps = lazy_container.fps();
+        let timestamp_ms = (frame_num as f64 * 1000.0 / fps) as u64;
+
+        let output_path = output.context("Output path required for frame extraction")?;
+        let mut compositor = LazyFrameCompositor::new(lazy_container);
+
+        println!("Extracting frame {} at {}ms", frame_num, timestamp_ms);
+        // Go through the seek index rather than render_frame directly: a
+        // fresh compositor has no `last_frame`, so a container encoded with
+        // `temporal_reference` (ReferenceMode::Previous entries) would
+        // otherwise render from black instead of the reconstructed reference
+        // chain.
+        let frame = compositor
+            .seek(timestamp_ms)
+            .context("Failed to render frame")?;
+
+        frame.save(&output_path).context("Failed to save frame")?;
+        println!("Saved frame to {}", output_path.display());
+        return Ok(());
+    }
+
     // Read VAI container
     let file = File::open(&input).context("Failed to open VAI file")?;
     let container = VaiContainer::read(file).context("Failed to read VAI container")?;
@@ -173,51 +306,123 @@ fn decode_video(
         }
     }
 
+    if let Some(path) = &extract_audio {
+        extract_audio_sidecar(&container, path)?;
+    }
+
     // Create compositor
     let mut compositor = FrameCompositor::new(container.clone());
 
-    if let Some(frame_num) = frame_num {
-        // Extract single frame
-        let output_path = output.context("Output path required for frame extraction")?;
-        
-        // Calculate timestamp for frame number
-        let fps = container.fps();
-        let timestamp_ms = (frame_num as f64 * 1000.0 / fps) as u64;
+    // Extract all frames (the single-frame case returned above unless
+    // --info was also passed, in which case there's nothing further to do)
+    let output_dir = output.context("Output directory required")?;
+    std::fs::create_dir_all(&output_dir).context("Failed to create output directory")?;
 
-        println!("Extracting frame {} at {}ms", frame_num, timestamp_ms);
+    if mux_audio {
+        extract_audio_sidecar(&container, &output_dir.join("audio.pcm"))?;
+    }
+
+    let fps = container.fps();
+    let frame_count = ((container.header.duration_ms as f64 * fps / 1000.0).floor() as u64).max(1);
+
+    println!("Extracting {} frames to {}", frame_count, output_dir.display());
+
+    for i in 0..frame_count {
+        let timestamp_ms = (i as f64 * 1000.0 / fps) as u64;
         let frame = compositor
             .render_frame(timestamp_ms)
             .context("Failed to render frame")?;
 
-        frame.save(&output_path).context("Failed to save frame")?;
-        println!("Saved frame to {}", output_path.display());
-    } else {
-        // Extract all frames
-        let output_dir = output.context("Output directory required")?;
-        std::fs::create_dir_all(&output_dir).context("Failed to create output directory")?;
+        let frame_path = output_dir.join(format!("frame_{:06}.png", i));
+        frame.save(&frame_path).context("Failed to save frame")?;
 
-        let fps = container.fps();
-        let frame_count = ((container.header.duration_ms as f64 * fps / 1000.0).floor() as u64).max(1);
+        if (i + 1) % 10 == 0 {
+            println!("Extracted {} / {} frames", i + 1, frame_count);
+        }
+    }
 
-        println!("Extracting {} frames to {}", frame_count, output_dir.display());
+    println!("Successfully extracted all frames");
 
-        for i in 0..frame_count {
-            let timestamp_ms = (i as f64 * 1000.0 / fps) as u64;
-            let frame = compositor
-                .render_frame(timestamp_ms)
-                .context("Failed to render frame")?;
+    Ok(())
+}
 
-            let frame_path = output_dir.join(format!("frame_{:06}.png", i));
-            frame.save(&frame_path).context("Failed to save frame")?;
+fn verify_video(input: PathBuf, source: PathBuf, csv: Option<PathBuf>) -> Result<()> {
+    println!("Verifying: {} against source {}", input.display(), source.display());
 
-            if (i + 1) % 10 == 0 {
-                println!("Extracted {} / {} frames", i + 1, frame_count);
-            }
+    let file = File::open(&input).context("Failed to open VAI file")?;
+    let container = VaiContainer::read(file).context("Failed to read VAI container")?;
+    let mut compositor = FrameCompositor::new(container);
+
+    let mut reader = VideoReader::open(
+        source.to_str().context("Invalid source path")?,
+    )
+    .context("Failed to open source video")?;
+
+    let mut csv_writer = csv
+        .map(|path| -> Result<_> {
+            let file = File::create(&path).context("Failed to create CSV report")?;
+            Ok(BufWriter::new(file))
+        })
+        .transpose()?;
+
+    if let Some(ref mut w) = csv_writer {
+        writeln!(w, "frame,pts_ms,psnr,ssim").context("Failed to write CSV header")?;
+    }
+
+    let mut psnr_sum = 0.0f64;
+    let mut psnr_min = f64::INFINITY;
+    let mut ssim_sum = 0.0f64;
+    let mut ssim_min = f64::INFINITY;
+    let mut frame_count = 0u64;
+
+    reader.read_frames_streaming(|frame_idx, pts_ms, source_frame| {
+        let vai_frame = compositor
+            .render_frame(pts_ms)
+            .map_err(|e| vai_encoder::Error::VideoEncode(e.to_string()))?;
+        let quality = compare_frames(&source_frame, &vai_frame);
+
+        psnr_sum += quality.psnr;
+        psnr_min = psnr_min.min(quality.psnr);
+        ssim_sum += quality.ssim;
+        ssim_min = ssim_min.min(quality.ssim);
+        frame_count += 1;
+
+        if let Some(ref mut w) = csv_writer {
+            writeln!(w, "{},{},{},{}", frame_idx, pts_ms, quality.psnr, quality.ssim)
+                .map_err(|e| vai_encoder::Error::VideoEncode(e.to_string()))?;
         }
 
-        println!("Successfully extracted all frames");
+        Ok(())
+    })
+    .context("Failed to decode source video")?;
+
+    if frame_count == 0 {
+        println!("No frames to compare");
+        return Ok(());
     }
 
+    println!("\n=== Quality Report ({} frames) ===", frame_count);
+    println!("PSNR: min {:.2} dB, mean {:.2} dB", psnr_min, psnr_sum / frame_count as f64);
+    println!("SSIM: min {:.4}, mean {:.4}", ssim_min, ssim_sum / frame_count as f64);
+
+    Ok(())
+}
+
+/// Writes the container's first audio asset's raw PCM bytes to `path`, or
+/// prints a notice if the file has no embedded audio track.
+fn extract_audio_sidecar(container: &VaiContainer, path: &PathBuf) -> Result<()> {
+    let Some(asset) = container.audio_assets.first() else {
+        println!("No audio track embedded in this VAI file");
+        return Ok(());
+    };
+
+    std::fs::write(path, &asset.data).context("Failed to write audio sidecar")?;
+    println!(
+        "Extracted audio track ({} Hz, {} ch) to {}",
+        asset.sample_rate,
+        asset.channels,
+        path.display()
+    );
     Ok(())
 }
 
@@ -272,4 +477,17 @@ fn print_info(container: &VaiContainer) {
     if container.timeline.len() > 10 {
         println!("  ... and {} more entries", container.timeline.len() - 10);
     }
+
+    if !container.audio_assets.is_empty() {
+        println!("\n=== Audio ===");
+        for asset in &container.audio_assets {
+            println!(
+                "  Audio asset {}: {} Hz, {} ch, {} bytes",
+                asset.id,
+                asset.sample_rate,
+                asset.channels,
+                asset.data_size()
+            );
+        }
+    }
 }
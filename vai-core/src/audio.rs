@@ -0,0 +1,69 @@
+//! Audio data structures for VAI format
+//!
+//! Mirrors [`crate::asset::Asset`] and [`crate::timeline::TimelineEntry`] for
+//! the optional audio track: an [`AudioAsset`] is one encoded audio blob, and
+//! an [`AudioTimelineEntry`] places it on the same millisecond clock the
+//! video timeline uses, so audio and video stay in sync without a second
+//! notion of time.
+
+/// A single encoded audio blob (e.g. one audio stream's samples)
+#[derive(Debug, Clone)]
+pub struct AudioAsset {
+    /// Unique identifier for this audio asset
+    pub id: u32,
+    /// Sample rate in Hz
+    pub sample_rate: u32,
+    /// Number of interleaved channels
+    pub channels: u8,
+    /// Encoded audio data
+    pub data: Vec<u8>,
+}
+
+impl AudioAsset {
+    /// Creates a new audio asset
+    pub fn new(id: u32, sample_rate: u32, channels: u8, data: Vec<u8>) -> Self {
+        Self {
+            id,
+            sample_rate,
+            channels,
+            data,
+        }
+    }
+
+    /// Returns the size of the audio data in bytes
+    pub fn data_size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+/// Places an [`AudioAsset`] on the video's millisecond timeline
+#[derive(Debug, Clone, Copy)]
+pub struct AudioTimelineEntry {
+    /// Audio asset ID to play
+    pub asset_id: u32,
+    /// Start time in milliseconds
+    pub start_time_ms: u64,
+    /// End time in milliseconds
+    pub end_time_ms: u64,
+}
+
+impl AudioTimelineEntry {
+    /// Creates a new audio timeline entry
+    pub fn new(asset_id: u32, start_time_ms: u64, end_time_ms: u64) -> Self {
+        Self {
+            asset_id,
+            start_time_ms,
+            end_time_ms,
+        }
+    }
+
+    /// Checks if this entry is active at the given timestamp
+    pub fn is_active(&self, timestamp_ms: u64) -> bool {
+        timestamp_ms >= self.start_time_ms && timestamp_ms < self.end_time_ms
+    }
+
+    /// Returns the duration of this entry in milliseconds
+    pub fn duration_ms(&self) -> u64 {
+        self.end_time_ms.saturating_sub(self.start_time_ms)
+    }
+}
@@ -0,0 +1,128 @@
+//! Box/TLV framing shared by `VaiContainer` and `LazyVaiContainer`
+//!
+//! A VAI file is a flat sequence of length-prefixed, FourCC-tagged boxes,
+//! the same idea ISO-BMFF (MP4) containers use: a `u32` total size followed
+//! by a 4-byte type tag, optionally followed by a "full box" prefix (`u8`
+//! version + `u24` flags). A reader loops over boxes until EOF, dispatches
+//! the tags it knows, and skips anything else by discarding `size - 8`
+//! bytes. New capabilities become new box types that old readers ignore
+//! instead of failing with `Error::UnsupportedVersion`.
+
+use crate::{Error, Result};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/// Container header: frame geometry, rate, and duration
+pub(crate) const TAG_HEADER: [u8; 4] = *b"vhdr";
+/// Asset section: a sequence of compressed image blobs
+pub(crate) const TAG_ASSETS: [u8; 4] = *b"asts";
+/// Asset index: a sequence of (asset id, byte offset, length) triples
+/// pointing into the `asts` box, for random-access asset fetches without
+/// scanning the asset section
+pub(crate) const TAG_ASSET_INDEX: [u8; 4] = *b"aidx";
+/// Timeline section: a sequence of timeline entries
+pub(crate) const TAG_TIMELINE: [u8; 4] = *b"tmln";
+/// Seek index section: a sequence of periodic seek entries
+pub(crate) const TAG_SEEK_INDEX: [u8; 4] = *b"sidx";
+/// Optional audio asset section: a sequence of encoded audio blobs
+pub(crate) const TAG_AUDIO_ASSETS: [u8; 4] = *b"aaud";
+/// Optional audio timeline section: a sequence of audio timeline entries,
+/// keyed to the same millisecond clock as `tmln`
+pub(crate) const TAG_AUDIO_TIMELINE: [u8; 4] = *b"atml";
+
+/// Size of a box's framing: `u32` size + 4-byte tag
+pub(crate) const BOX_HEADER_LEN: u64 = 8;
+/// Size of a full box's version + 24-bit flags word, on top of `BOX_HEADER_LEN`
+pub(crate) const FULL_BOX_PREFIX_LEN: u64 = 4;
+
+/// A box's framing, read off the front of its on-disk bytes
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BoxHeader {
+    /// Total on-disk size of the box, including this 8-byte header
+    pub size: u32,
+    /// 4-byte type tag, e.g. `TAG_HEADER`
+    pub tag: [u8; 4],
+}
+
+impl BoxHeader {
+    /// Reads a box header, returning `Ok(None)` on a clean EOF before any
+    /// bytes are read at all (the normal way a box stream ends)
+    pub fn read<R: Read>(reader: &mut R) -> Result<Option<Self>> {
+        let mut size_buf = [0u8; 4];
+        let mut read = 0;
+        while read < size_buf.len() {
+            let n = reader.read(&mut size_buf[read..])?;
+            if n == 0 {
+                if read == 0 {
+                    return Ok(None);
+                }
+                return Err(Error::Io(std::io::Error::from(
+                    std::io::ErrorKind::UnexpectedEof,
+                )));
+            }
+            read += n;
+        }
+        let size = u32::from_le_bytes(size_buf);
+        if (size as u64) < BOX_HEADER_LEN {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "box size smaller than its own header",
+            )));
+        }
+
+        let mut tag = [0u8; 4];
+        reader.read_exact(&mut tag)?;
+
+        Ok(Some(Self { size, tag }))
+    }
+
+    /// Number of content bytes following this header
+    pub fn content_len(&self) -> u64 {
+        self.size as u64 - BOX_HEADER_LEN
+    }
+}
+
+/// Writes a box header for a box whose content (including any full-box
+/// prefix) is `content_len` bytes long
+pub(crate) fn write_box_header<W: Write>(
+    writer: &mut W,
+    tag: [u8; 4],
+    content_len: u64,
+) -> Result<()> {
+    writer.write_u32::<LittleEndian>((BOX_HEADER_LEN + content_len) as u32)?;
+    writer.write_all(&tag)?;
+    Ok(())
+}
+
+/// Reads a full box's `u8` version + `u24` flags word, ISO-BMFF style
+pub(crate) fn read_full_box_prefix<R: Read>(reader: &mut R) -> Result<(u8, u32)> {
+    let version = reader.read_u8()?;
+    let mut flag_bytes = [0u8; 3];
+    reader.read_exact(&mut flag_bytes)?;
+    let flags = u32::from_be_bytes([0, flag_bytes[0], flag_bytes[1], flag_bytes[2]]);
+    Ok((version, flags))
+}
+
+/// Writes a full box's `u8` version + `u24` flags word
+pub(crate) fn write_full_box_prefix<W: Write>(
+    writer: &mut W,
+    version: u8,
+    flags: u32,
+) -> Result<()> {
+    writer.write_u8(version)?;
+    writer.write_all(&flags.to_be_bytes()[1..])?;
+    Ok(())
+}
+
+/// Skips a box's remaining content by discarding `len` bytes, for tags a
+/// reader doesn't recognize
+pub(crate) fn skip_box_content<R: Read>(reader: &mut R, len: u64) -> Result<()> {
+    let mut remaining = len;
+    let mut buf = [0u8; 4096];
+    while remaining > 0 {
+        let chunk = remaining.min(buf.len() as u64) as usize;
+        reader.read_exact(&mut buf[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    Ok(())
+}
@@ -1,20 +1,295 @@
 //! VAI container format serialization and deserialization
-
-use crate::{Asset, Error, Result, TimelineEntry};
+//!
+//! On disk, a VAI file is a sequence of boxes (see [`crate::boxes`]): a
+//! `vhdr` full box carrying the frame geometry and duration, an `aidx` full
+//! box mapping each asset id to its byte offset and record length (so a
+//! random-access reader never has to scan the section that follows), an
+//! `asts` full box carrying the asset blobs, a `tmln` full box carrying the
+//! timeline, and a `sidx` full box carrying the seek index. None of these
+//! boxes carry their own entry counts — each is parsed by reading records
+//! until the box's declared content length is exhausted, so a future box
+//! type (transforms, color metadata) can be added without touching the ones
+//! that already exist, and an old reader that doesn't know a new tag simply
+//! skips it. An optional `aaud`/`atml` pair carries an audio track on the
+//! same millisecond clock as `tmln`; video-only files simply omit them.
+
+use crate::boxes::{
+    self, read_full_box_prefix, write_box_header, write_full_box_prefix, BoxHeader,
+    FULL_BOX_PREFIX_LEN, TAG_ASSETS, TAG_ASSET_INDEX, TAG_AUDIO_ASSETS, TAG_AUDIO_TIMELINE,
+    TAG_HEADER, TAG_SEEK_INDEX, TAG_TIMELINE,
+};
+use crate::seek_index::{self, SeekIndexEntry};
+use crate::{
+    Asset, AudioAsset, AudioTimelineEntry, BlendMode, Error, ReferenceMode, Result, TimelineEntry,
+    Transform,
+};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use std::io::{Read, Write};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+
+/// Current box format version
+///
+/// Bumped to 2 when timeline entries grew a transform, opacity, and blend
+/// mode; unlike a new box tag (which an old reader can just skip), this
+/// changes an *existing* box's fixed-size record layout, so it isn't safe
+/// for an older reader to parse at all.
+const VERSION: u8 = 2;
+
+/// Size of the `vhdr` box's content: width + height + fps_num + fps_den + duration_ms
+const HEADER_CONTENT_LEN: u64 = 4 + 4 + 4 + 4 + 8;
+
+/// Size of an asset record's fixed-size fields (id + width + height +
+/// data_len), before its variable-length data
+pub(crate) const ASSET_RECORD_PREFIX: u64 = 4 + 4 + 4 + 4;
+
+/// Size of a timeline entry record, on disk: asset_id + start/end time +
+/// position + z_order + reference_mode + transform (scale_x/scale_y/rotation/
+/// translate_x/translate_y) + opacity + blend_mode
+const TIMELINE_ENTRY_LEN: u64 = 4 + 8 + 8 + 4 + 4 + 4 + 1 + 4 + 4 + 4 + 4 + 4 + 1 + 1;
+
+/// Size of one `aidx` entry: asset id + byte offset + record length
+const ASSET_INDEX_ENTRY_LEN: u64 = 4 + 8 + 4;
+
+/// Size of an audio asset record's fixed-size fields (id + sample_rate +
+/// channels + data_len), before its variable-length data
+const AUDIO_ASSET_RECORD_PREFIX: u64 = 4 + 4 + 1 + 4;
+
+/// Size of an audio timeline entry record: asset_id + start/end time
+const AUDIO_TIMELINE_ENTRY_LEN: u64 = 4 + 8 + 8;
+
+/// Total on-disk size of the `vhdr` box
+fn header_box_len() -> u64 {
+    boxes::BOX_HEADER_LEN + FULL_BOX_PREFIX_LEN + HEADER_CONTENT_LEN
+}
+
+/// Total on-disk size of the `aidx` box for `num_assets` assets
+fn asset_index_box_len(num_assets: usize) -> u64 {
+    boxes::BOX_HEADER_LEN + FULL_BOX_PREFIX_LEN + ASSET_INDEX_ENTRY_LEN * num_assets as u64
+}
 
-/// Magic bytes for VAI format: "VAI\0"
-const MAGIC: [u8; 4] = [b'V', b'A', b'I', 0];
+/// Byte offset of the first asset record, i.e. right after the `vhdr` box,
+/// the `aidx` box, and the `asts` box's own header + full-box prefix
+///
+/// The `aidx` box is written before `asts` precisely so a random-access
+/// reader can learn every asset's offset before deciding whether it even
+/// needs to look at the (potentially huge) `asts` box at all.
+fn first_asset_offset(num_assets: usize) -> u64 {
+    header_box_len() + asset_index_box_len(num_assets) + boxes::BOX_HEADER_LEN + FULL_BOX_PREFIX_LEN
+}
+
+/// Writes one timeline entry in its on-disk form (`TIMELINE_ENTRY_LEN` bytes)
+fn write_timeline_entry<W: Write>(writer: &mut W, entry: &TimelineEntry) -> Result<()> {
+    writer.write_u32::<LittleEndian>(entry.asset_id)?;
+    writer.write_u64::<LittleEndian>(entry.start_time_ms)?;
+    writer.write_u64::<LittleEndian>(entry.end_time_ms)?;
+    writer.write_i32::<LittleEndian>(entry.position_x)?;
+    writer.write_i32::<LittleEndian>(entry.position_y)?;
+    writer.write_i32::<LittleEndian>(entry.z_order)?;
+    writer.write_u8(entry.reference_mode.to_u8())?;
+    writer.write_i32::<LittleEndian>(entry.transform.scale_x)?;
+    writer.write_i32::<LittleEndian>(entry.transform.scale_y)?;
+    writer.write_i32::<LittleEndian>(entry.transform.rotation)?;
+    writer.write_i32::<LittleEndian>(entry.transform.translate_x)?;
+    writer.write_i32::<LittleEndian>(entry.transform.translate_y)?;
+    writer.write_u8(entry.opacity)?;
+    writer.write_u8(entry.blend_mode.to_u8())?;
+    Ok(())
+}
 
-/// Current VAI format version
-const VERSION: u16 = 1;
+/// Reads one timeline entry in its on-disk form
+fn read_timeline_entry<R: Read>(reader: &mut R) -> Result<TimelineEntry> {
+    let asset_id = reader.read_u32::<LittleEndian>()?;
+    let start_time_ms = reader.read_u64::<LittleEndian>()?;
+    let end_time_ms = reader.read_u64::<LittleEndian>()?;
+    let position_x = reader.read_i32::<LittleEndian>()?;
+    let position_y = reader.read_i32::<LittleEndian>()?;
+    let z_order = reader.read_i32::<LittleEndian>()?;
+    let reference_mode = ReferenceMode::from_u8(reader.read_u8()?);
+    let transform = Transform {
+        scale_x: reader.read_i32::<LittleEndian>()?,
+        scale_y: reader.read_i32::<LittleEndian>()?,
+        rotation: reader.read_i32::<LittleEndian>()?,
+        translate_x: reader.read_i32::<LittleEndian>()?,
+        translate_y: reader.read_i32::<LittleEndian>()?,
+    };
+    let opacity = reader.read_u8()?;
+    let blend_mode = BlendMode::from_u8(reader.read_u8()?);
+
+    Ok(TimelineEntry::with_transform(
+        asset_id,
+        start_time_ms,
+        end_time_ms,
+        position_x,
+        position_y,
+        z_order,
+        reference_mode,
+        transform,
+        opacity,
+        blend_mode,
+    ))
+}
+
+/// Reads every timeline entry packed into a `tmln` box's content, shared by
+/// the eager and lazy container readers
+pub(crate) fn read_timeline_box_content(content: &[u8]) -> Result<Vec<TimelineEntry>> {
+    let mut cursor = Cursor::new(content);
+    let mut timeline = Vec::new();
+    while (cursor.position() as usize) < content.len() {
+        timeline.push(read_timeline_entry(&mut cursor)?);
+    }
+    Ok(timeline)
+}
+
+/// Reads every seek index entry packed into a `sidx` box's content, shared
+/// by the eager and lazy container readers
+pub(crate) fn read_seek_index_box_content(content: &[u8]) -> Result<Vec<SeekIndexEntry>> {
+    let mut cursor = Cursor::new(content);
+    let mut seek_index = Vec::new();
+    while (cursor.position() as usize) < content.len() {
+        let timestamp_ms = cursor.read_u64::<LittleEndian>()?;
+        let byte_offset = cursor.read_u64::<LittleEndian>()?;
+        let num_indices = cursor.read_u32::<LittleEndian>()?;
+
+        let mut timeline_indices = Vec::with_capacity(num_indices as usize);
+        for _ in 0..num_indices {
+            timeline_indices.push(cursor.read_u32::<LittleEndian>()?);
+        }
+
+        seek_index.push(SeekIndexEntry {
+            timestamp_ms,
+            byte_offset,
+            timeline_indices,
+        });
+    }
+    Ok(seek_index)
+}
+
+/// Writes the `tmln` full box for `timeline`
+fn write_timeline_box<W: Write>(writer: &mut W, timeline: &[TimelineEntry]) -> Result<()> {
+    let content_len = FULL_BOX_PREFIX_LEN + TIMELINE_ENTRY_LEN * timeline.len() as u64;
+    write_box_header(writer, TAG_TIMELINE, content_len)?;
+    write_full_box_prefix(writer, VERSION, 0)?;
+    for entry in timeline {
+        write_timeline_entry(writer, entry)?;
+    }
+    Ok(())
+}
+
+/// Writes the `sidx` full box for `seek_index`
+fn write_seek_index_box<W: Write>(writer: &mut W, seek_index: &[SeekIndexEntry]) -> Result<()> {
+    let entries_len: u64 = seek_index
+        .iter()
+        .map(|e| 8 + 8 + 4 + 4 * e.timeline_indices.len() as u64)
+        .sum();
+    write_box_header(writer, TAG_SEEK_INDEX, FULL_BOX_PREFIX_LEN + entries_len)?;
+    write_full_box_prefix(writer, VERSION, 0)?;
+    for entry in seek_index {
+        writer.write_u64::<LittleEndian>(entry.timestamp_ms)?;
+        writer.write_u64::<LittleEndian>(entry.byte_offset)?;
+        writer.write_u32::<LittleEndian>(entry.timeline_indices.len() as u32)?;
+        for &idx in &entry.timeline_indices {
+            writer.write_u32::<LittleEndian>(idx)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads every audio timeline entry packed into an `atml` box's content
+/// (past the full-box prefix), shared by the eager and lazy container readers
+pub(crate) fn read_audio_timeline_box_content(content: &[u8]) -> Result<Vec<AudioTimelineEntry>> {
+    let mut cursor = Cursor::new(content);
+    let mut timeline = Vec::new();
+    while (cursor.position() as usize) < content.len() {
+        let asset_id = cursor.read_u32::<LittleEndian>()?;
+        let start_time_ms = cursor.read_u64::<LittleEndian>()?;
+        let end_time_ms = cursor.read_u64::<LittleEndian>()?;
+        timeline.push(AudioTimelineEntry::new(asset_id, start_time_ms, end_time_ms));
+    }
+    Ok(timeline)
+}
+
+/// Writes the `aaud` full box for `audio_assets`
+fn write_audio_assets_box<W: Write>(writer: &mut W, audio_assets: &[AudioAsset]) -> Result<()> {
+    let content_len: u64 = FULL_BOX_PREFIX_LEN
+        + audio_assets
+            .iter()
+            .map(|a| AUDIO_ASSET_RECORD_PREFIX + a.data.len() as u64)
+            .sum::<u64>();
+    write_box_header(writer, TAG_AUDIO_ASSETS, content_len)?;
+    write_full_box_prefix(writer, VERSION, 0)?;
+    for asset in audio_assets {
+        writer.write_u32::<LittleEndian>(asset.id)?;
+        writer.write_u32::<LittleEndian>(asset.sample_rate)?;
+        writer.write_u8(asset.channels)?;
+        writer.write_u32::<LittleEndian>(asset.data.len() as u32)?;
+        writer.write_all(&asset.data)?;
+    }
+    Ok(())
+}
+
+/// Writes the `atml` full box for `audio_timeline`
+fn write_audio_timeline_box<W: Write>(
+    writer: &mut W,
+    audio_timeline: &[AudioTimelineEntry],
+) -> Result<()> {
+    let content_len = FULL_BOX_PREFIX_LEN + AUDIO_TIMELINE_ENTRY_LEN * audio_timeline.len() as u64;
+    write_box_header(writer, TAG_AUDIO_TIMELINE, content_len)?;
+    write_full_box_prefix(writer, VERSION, 0)?;
+    for entry in audio_timeline {
+        writer.write_u32::<LittleEndian>(entry.asset_id)?;
+        writer.write_u64::<LittleEndian>(entry.start_time_ms)?;
+        writer.write_u64::<LittleEndian>(entry.end_time_ms)?;
+    }
+    Ok(())
+}
+
+/// Finds the seek index entry at or before `timestamp_ms`, shared by
+/// `VaiContainer` and `LazyVaiContainer`
+pub(crate) fn find_seek_index_entry(
+    seek_index: &[SeekIndexEntry],
+    timestamp_ms: u64,
+) -> Option<&SeekIndexEntry> {
+    match seek_index.binary_search_by_key(&timestamp_ms, |e| e.timestamp_ms) {
+        Ok(i) => seek_index.get(i),
+        Err(0) => None,
+        Err(i) => seek_index.get(i - 1),
+    }
+}
+
+/// Maps each asset's id to the byte offset of its record within the
+/// container file, in write order
+fn asset_byte_offsets(assets: &[Asset]) -> HashMap<u32, u64> {
+    let mut offsets = HashMap::with_capacity(assets.len());
+    let mut offset = first_asset_offset(assets.len());
+    for asset in assets {
+        offsets.insert(asset.id, offset);
+        offset += ASSET_RECORD_PREFIX + asset.data.len() as u64;
+    }
+    offsets
+}
+
+/// Writes the `aidx` full box: one (id, byte offset, record length) triple
+/// per asset, so a random-access reader can fetch any asset's bytes
+/// directly without ever scanning the `asts` box
+fn write_asset_index_box<W: Write>(writer: &mut W, assets: &[Asset]) -> Result<()> {
+    let offsets = asset_byte_offsets(assets);
+    let content_len = FULL_BOX_PREFIX_LEN + ASSET_INDEX_ENTRY_LEN * assets.len() as u64;
+    write_box_header(writer, TAG_ASSET_INDEX, content_len)?;
+    write_full_box_prefix(writer, VERSION, 0)?;
+    for asset in assets {
+        let record_len = ASSET_RECORD_PREFIX + asset.data.len() as u64;
+        writer.write_u32::<LittleEndian>(asset.id)?;
+        writer.write_u64::<LittleEndian>(offsets[&asset.id])?;
+        writer.write_u32::<LittleEndian>(record_len as u32)?;
+    }
+    Ok(())
+}
 
 /// VAI file header
 #[derive(Debug, Clone)]
 pub struct VaiHeader {
-    /// Format version
-    pub version: u16,
+    /// `vhdr` box version
+    pub version: u8,
     /// Frame width in pixels
     pub width: u32,
     /// Frame height in pixels
@@ -25,23 +300,11 @@ pub struct VaiHeader {
     pub fps_den: u32,
     /// Total duration in milliseconds
     pub duration_ms: u64,
-    /// Number of assets
-    pub num_assets: u32,
-    /// Number of timeline entries
-    pub num_timeline_entries: u32,
 }
 
 impl VaiHeader {
     /// Creates a new VAI header
-    pub fn new(
-        width: u32,
-        height: u32,
-        fps_num: u32,
-        fps_den: u32,
-        duration_ms: u64,
-        num_assets: u32,
-        num_timeline_entries: u32,
-    ) -> Self {
+    pub fn new(width: u32, height: u32, fps_num: u32, fps_den: u32, duration_ms: u64) -> Self {
         Self {
             version: VERSION,
             width,
@@ -49,34 +312,21 @@ impl VaiHeader {
             fps_num,
             fps_den,
             duration_ms,
-            num_assets,
-            num_timeline_entries,
         }
     }
 
-    /// Reads a header from a reader
-    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
-        // Read and validate magic bytes
-        let mut magic = [0u8; 4];
-        reader.read_exact(&mut magic)?;
-        if magic != MAGIC {
-            return Err(Error::InvalidMagic);
-        }
-
-        // Read version
-        let version = reader.read_u16::<LittleEndian>()?;
+    /// Reads a header from a `vhdr` box's content
+    fn from_box_content<R: Read>(mut reader: R) -> Result<Self> {
+        let (version, _flags) = read_full_box_prefix(&mut reader)?;
         if version != VERSION {
-            return Err(Error::UnsupportedVersion(version));
+            return Err(Error::UnsupportedVersion(version as u16));
         }
 
-        // Read remaining header fields
         let width = reader.read_u32::<LittleEndian>()?;
         let height = reader.read_u32::<LittleEndian>()?;
         let fps_num = reader.read_u32::<LittleEndian>()?;
         let fps_den = reader.read_u32::<LittleEndian>()?;
         let duration_ms = reader.read_u64::<LittleEndian>()?;
-        let num_assets = reader.read_u32::<LittleEndian>()?;
-        let num_timeline_entries = reader.read_u32::<LittleEndian>()?;
 
         Ok(Self {
             version,
@@ -85,22 +335,30 @@ impl VaiHeader {
             fps_num,
             fps_den,
             duration_ms,
-            num_assets,
-            num_timeline_entries,
         })
     }
 
-    /// Writes the header to a writer
+    /// Reads a `vhdr` box (framing and all) from a reader
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let box_header = BoxHeader::read(reader)?.ok_or(Error::InvalidMagic)?;
+        if box_header.tag != TAG_HEADER {
+            return Err(Error::InvalidMagic);
+        }
+
+        let mut content = vec![0u8; box_header.content_len() as usize];
+        reader.read_exact(&mut content)?;
+        Self::from_box_content(Cursor::new(content))
+    }
+
+    /// Writes this header as a `vhdr` box, framing and all
     pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
-        writer.write_all(&MAGIC)?;
-        writer.write_u16::<LittleEndian>(self.version)?;
+        write_box_header(writer, TAG_HEADER, FULL_BOX_PREFIX_LEN + HEADER_CONTENT_LEN)?;
+        write_full_box_prefix(writer, self.version, 0)?;
         writer.write_u32::<LittleEndian>(self.width)?;
         writer.write_u32::<LittleEndian>(self.height)?;
         writer.write_u32::<LittleEndian>(self.fps_num)?;
         writer.write_u32::<LittleEndian>(self.fps_den)?;
         writer.write_u64::<LittleEndian>(self.duration_ms)?;
-        writer.write_u32::<LittleEndian>(self.num_assets)?;
-        writer.write_u32::<LittleEndian>(self.num_timeline_entries)?;
         Ok(())
     }
 }
@@ -114,66 +372,150 @@ pub struct VaiContainer {
     pub assets: Vec<Asset>,
     /// Timeline entries
     pub timeline: Vec<TimelineEntry>,
+    /// Seek index for random-access scrubbing, built from `timeline`
+    pub seek_index: Vec<SeekIndexEntry>,
+    /// Optional audio assets. Empty for video-only files.
+    pub audio_assets: Vec<AudioAsset>,
+    /// Optional audio timeline, on the same millisecond clock as `timeline`.
+    /// Empty for video-only files.
+    pub audio_timeline: Vec<AudioTimelineEntry>,
 }
 
 impl VaiContainer {
-    /// Creates a new VAI container
+    /// Creates a new, video-only VAI container
+    ///
+    /// The seek index is derived from `timeline` and `header.duration_ms`.
     pub fn new(header: VaiHeader, assets: Vec<Asset>, timeline: Vec<TimelineEntry>) -> Self {
+        Self::with_audio(header, assets, timeline, Vec::new(), Vec::new())
+    }
+
+    /// Creates a new VAI container with an optional audio track
+    ///
+    /// The seek index is derived from `timeline` and `header.duration_ms`.
+    pub fn with_audio(
+        header: VaiHeader,
+        assets: Vec<Asset>,
+        timeline: Vec<TimelineEntry>,
+        audio_assets: Vec<AudioAsset>,
+        audio_timeline: Vec<AudioTimelineEntry>,
+    ) -> Self {
+        let asset_offsets = asset_byte_offsets(&assets);
+        let seek_index = seek_index::build_seek_index(
+            &timeline,
+            &asset_offsets,
+            header.duration_ms,
+            seek_index::SEEK_INDEX_INTERVAL_MS,
+        );
+
         Self {
             header,
             assets,
             timeline,
+            seek_index,
+            audio_assets,
+            audio_timeline,
         }
     }
 
     /// Reads a VAI container from a reader
+    ///
+    /// Loops over boxes until EOF, dispatching the tags this version knows
+    /// (`vhdr`, `aidx`, `asts`, `tmln`, `sidx`, and the optional `aaud`/
+    /// `atml` audio pair) and skipping anything else.
     pub fn read<R: Read>(mut reader: R) -> Result<Self> {
-        // Read header
-        let header = VaiHeader::read(&mut reader)?;
-
-        // Read assets
-        let mut assets = Vec::with_capacity(header.num_assets as usize);
-        for _ in 0..header.num_assets {
-            let id = reader.read_u32::<LittleEndian>()?;
-            let width = reader.read_u32::<LittleEndian>()?;
-            let height = reader.read_u32::<LittleEndian>()?;
-            let data_len = reader.read_u32::<LittleEndian>()?;
-
-            let mut data = vec![0u8; data_len as usize];
-            reader.read_exact(&mut data)?;
-
-            assets.push(Asset::new(id, width, height, data));
+        let mut header = None;
+        let mut assets = Vec::new();
+        let mut timeline = Vec::new();
+        let mut seek_index = Vec::new();
+        let mut audio_assets = Vec::new();
+        let mut audio_timeline = Vec::new();
+
+        while let Some(box_header) = BoxHeader::read(&mut reader)? {
+            let mut content = vec![0u8; box_header.content_len() as usize];
+            reader.read_exact(&mut content)?;
+
+            match box_header.tag {
+                TAG_HEADER => header = Some(VaiHeader::from_box_content(Cursor::new(content))?),
+                TAG_ASSETS => {
+                    let mut cursor = Cursor::new(&content);
+                    let (_version, _flags) = read_full_box_prefix(&mut cursor)?;
+                    while (cursor.position() as usize) < content.len() {
+                        let id = cursor.read_u32::<LittleEndian>()?;
+                        let width = cursor.read_u32::<LittleEndian>()?;
+                        let height = cursor.read_u32::<LittleEndian>()?;
+                        let data_len = cursor.read_u32::<LittleEndian>()?;
+                        let mut data = vec![0u8; data_len as usize];
+                        cursor.read_exact(&mut data)?;
+                        assets.push(Asset::new(id, width, height, data));
+                    }
+                }
+                TAG_TIMELINE => {
+                    // First FULL_BOX_PREFIX_LEN bytes are the version/flags word;
+                    // this version doesn't need either to parse the entries.
+                    timeline = read_timeline_box_content(&content[FULL_BOX_PREFIX_LEN as usize..])?;
+                }
+                TAG_SEEK_INDEX => {
+                    seek_index =
+                        read_seek_index_box_content(&content[FULL_BOX_PREFIX_LEN as usize..])?;
+                }
+                TAG_ASSET_INDEX => {
+                    // Only useful for a random-access reader that wants to skip
+                    // straight past `asts`; this reader loads every asset anyway.
+                }
+                TAG_AUDIO_ASSETS => {
+                    let mut cursor = Cursor::new(&content);
+                    let (_version, _flags) = read_full_box_prefix(&mut cursor)?;
+                    while (cursor.position() as usize) < content.len() {
+                        let id = cursor.read_u32::<LittleEndian>()?;
+                        let sample_rate = cursor.read_u32::<LittleEndian>()?;
+                        let channels = cursor.read_u8()?;
+                        let data_len = cursor.read_u32::<LittleEndian>()?;
+                        let mut data = vec![0u8; data_len as usize];
+                        cursor.read_exact(&mut data)?;
+                        audio_assets.push(AudioAsset::new(id, sample_rate, channels, data));
+                    }
+                }
+                TAG_AUDIO_TIMELINE => {
+                    audio_timeline = read_audio_timeline_box_content(
+                        &content[FULL_BOX_PREFIX_LEN as usize..],
+                    )?;
+                }
+                _ => {
+                    // Unknown box from a newer writer: already consumed above, so
+                    // there's nothing left to do but move on to the next one.
+                }
+            }
         }
 
-        // Read timeline entries
-        let mut timeline = Vec::with_capacity(header.num_timeline_entries as usize);
-        for _ in 0..header.num_timeline_entries {
-            let asset_id = reader.read_u32::<LittleEndian>()?;
-            let start_time_ms = reader.read_u64::<LittleEndian>()?;
-            let end_time_ms = reader.read_u64::<LittleEndian>()?;
-            let position_x = reader.read_i32::<LittleEndian>()?;
-            let position_y = reader.read_i32::<LittleEndian>()?;
-            let z_order = reader.read_i32::<LittleEndian>()?;
-
-            timeline.push(TimelineEntry::new(
-                asset_id,
-                start_time_ms,
-                end_time_ms,
-                position_x,
-                position_y,
-                z_order,
-            ));
-        }
+        let header = header.ok_or(Error::InvalidMagic)?;
 
-        Ok(Self::new(header, assets, timeline))
+        Ok(Self {
+            header,
+            assets,
+            timeline,
+            seek_index,
+            audio_assets,
+            audio_timeline,
+        })
     }
 
     /// Writes the VAI container to a writer
+    ///
+    /// The `aaud`/`atml` audio pair is only written when `audio_assets` is
+    /// non-empty, so a video-only container round-trips to the exact same
+    /// bytes it always has.
     pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
-        // Write header
         self.header.write(&mut writer)?;
-
-        // Write assets
+        write_asset_index_box(&mut writer, &self.assets)?;
+
+        let assets_content_len: u64 = FULL_BOX_PREFIX_LEN
+            + self
+                .assets
+                .iter()
+                .map(|a| ASSET_RECORD_PREFIX + a.data.len() as u64)
+                .sum::<u64>();
+        write_box_header(&mut writer, TAG_ASSETS, assets_content_len)?;
+        write_full_box_prefix(&mut writer, self.header.version, 0)?;
         for asset in &self.assets {
             writer.write_u32::<LittleEndian>(asset.id)?;
             writer.write_u32::<LittleEndian>(asset.width)?;
@@ -182,19 +524,23 @@ impl VaiContainer {
             writer.write_all(&asset.data)?;
         }
 
-        // Write timeline entries
-        for entry in &self.timeline {
-            writer.write_u32::<LittleEndian>(entry.asset_id)?;
-            writer.write_u64::<LittleEndian>(entry.start_time_ms)?;
-            writer.write_u64::<LittleEndian>(entry.end_time_ms)?;
-            writer.write_i32::<LittleEndian>(entry.position_x)?;
-            writer.write_i32::<LittleEndian>(entry.position_y)?;
-            writer.write_i32::<LittleEndian>(entry.z_order)?;
+        write_timeline_box(&mut writer, &self.timeline)?;
+        write_seek_index_box(&mut writer, &self.seek_index)?;
+
+        if !self.audio_assets.is_empty() {
+            write_audio_assets_box(&mut writer, &self.audio_assets)?;
+            write_audio_timeline_box(&mut writer, &self.audio_timeline)?;
         }
 
         Ok(())
     }
 
+    /// Finds the seek index entry at or before `timestamp_ms`, for O(log n)
+    /// random-access seeking instead of replaying from the start
+    pub fn find_seek_index(&self, timestamp_ms: u64) -> Option<&SeekIndexEntry> {
+        find_seek_index_entry(&self.seek_index, timestamp_ms)
+    }
+
     /// Gets an asset by ID
     pub fn get_asset(&self, id: u32) -> Option<&Asset> {
         self.assets.iter().find(|a| a.id == id)
@@ -226,7 +572,7 @@ mod tests {
 
     #[test]
     fn test_header_roundtrip() {
-        let header = VaiHeader::new(1920, 1080, 30, 1, 5000, 10, 20);
+        let header = VaiHeader::new(1920, 1080, 30, 1, 5000);
 
         let mut buffer = Vec::new();
         header.write(&mut buffer).unwrap();
@@ -240,13 +586,11 @@ mod tests {
         assert_eq!(header.fps_num, read_header.fps_num);
         assert_eq!(header.fps_den, read_header.fps_den);
         assert_eq!(header.duration_ms, read_header.duration_ms);
-        assert_eq!(header.num_assets, read_header.num_assets);
-        assert_eq!(header.num_timeline_entries, read_header.num_timeline_entries);
     }
 
     #[test]
     fn test_container_roundtrip() {
-        let header = VaiHeader::new(1920, 1080, 30, 1, 1000, 1, 1);
+        let header = VaiHeader::new(1920, 1080, 30, 1, 1000);
         let assets = vec![Asset::new(0, 100, 100, vec![1, 2, 3, 4])];
         let timeline = vec![TimelineEntry::new(0, 0, 1000, 0, 0, 0)];
 
@@ -260,5 +604,119 @@ mod tests {
         assert_eq!(container.header.width, read_container.header.width);
         assert_eq!(container.assets.len(), read_container.assets.len());
         assert_eq!(container.timeline.len(), read_container.timeline.len());
+        assert_eq!(container.seek_index.len(), read_container.seek_index.len());
+    }
+
+    #[test]
+    fn test_find_seek_index() {
+        let header = VaiHeader::new(640, 480, 30, 1, 3500);
+        let assets = vec![Asset::new(0, 640, 480, vec![0, 1, 2, 3])];
+        let timeline = vec![TimelineEntry::new(0, 0, 3500, 0, 0, 0)];
+
+        let container = VaiContainer::new(header, assets, timeline);
+
+        // Exactly on the first entry's timestamp: an exact match
+        assert!(container.find_seek_index(0).is_some());
+        // Somewhere in the middle: floors to the entry at or before it
+        let entry = container.find_seek_index(2500).unwrap();
+        assert!(entry.timestamp_ms <= 2500);
+    }
+
+    #[test]
+    fn test_unknown_box_is_skipped() {
+        // A reader from this version should tolerate a box type it doesn't
+        // recognize, wherever it appears in the stream.
+        let header = VaiHeader::new(320, 240, 25, 1, 1000);
+        let assets = vec![Asset::new(0, 320, 240, vec![9, 9])];
+        let timeline = vec![TimelineEntry::new(0, 0, 1000, 0, 0, 0)];
+        let container = VaiContainer::new(header, assets, timeline);
+
+        let mut buffer = Vec::new();
+        container.write(&mut buffer).unwrap();
+
+        // Splice in an unknown "xtra" box right after the vhdr box.
+        let vhdr_len = (u32::from_le_bytes(buffer[0..4].try_into().unwrap())) as usize;
+        let mut spliced = buffer[..vhdr_len].to_vec();
+        write_box_header(&mut spliced, *b"xtra", 4).unwrap();
+        spliced.extend_from_slice(&[1, 2, 3, 4]);
+        spliced.extend_from_slice(&buffer[vhdr_len..]);
+
+        let read_container = VaiContainer::read(Cursor::new(spliced)).unwrap();
+        assert_eq!(read_container.assets.len(), 1);
+        assert_eq!(read_container.timeline.len(), 1);
+    }
+
+    #[test]
+    fn test_video_only_container_has_no_audio_boxes() {
+        // A video-only container must write exactly the bytes it always
+        // has, with no `aaud`/`atml` boxes tacked on.
+        let header = VaiHeader::new(320, 240, 30, 1, 1000);
+        let assets = vec![Asset::new(0, 320, 240, vec![1, 2, 3])];
+        let timeline = vec![TimelineEntry::new(0, 0, 1000, 0, 0, 0)];
+
+        let with_new = VaiContainer::new(header.clone(), assets.clone(), timeline.clone());
+        let mut buffer_new = Vec::new();
+        with_new.write(&mut buffer_new).unwrap();
+
+        let with_audio = VaiContainer::with_audio(header, assets, timeline, Vec::new(), Vec::new());
+        let mut buffer_with_audio = Vec::new();
+        with_audio.write(&mut buffer_with_audio).unwrap();
+
+        assert_eq!(buffer_new, buffer_with_audio);
+
+        let read_back = VaiContainer::read(Cursor::new(buffer_new)).unwrap();
+        assert!(read_back.audio_assets.is_empty());
+        assert!(read_back.audio_timeline.is_empty());
+    }
+
+    #[test]
+    fn test_audio_roundtrip() {
+        let header = VaiHeader::new(320, 240, 30, 1, 2000);
+        let assets = vec![Asset::new(0, 320, 240, vec![1, 2, 3])];
+        let timeline = vec![TimelineEntry::new(0, 0, 2000, 0, 0, 0)];
+        let audio_assets = vec![AudioAsset::new(0, 16_000, 1, vec![9u8; 32])];
+        let audio_timeline = vec![AudioTimelineEntry::new(0, 0, 2000)];
+
+        let container =
+            VaiContainer::with_audio(header, assets, timeline, audio_assets, audio_timeline);
+
+        let mut buffer = Vec::new();
+        container.write(&mut buffer).unwrap();
+
+        let read_container = VaiContainer::read(Cursor::new(buffer)).unwrap();
+
+        assert_eq!(read_container.audio_assets.len(), 1);
+        assert_eq!(read_container.audio_assets[0].sample_rate, 16_000);
+        assert_eq!(read_container.audio_assets[0].channels, 1);
+        assert_eq!(read_container.audio_assets[0].data, vec![9u8; 32]);
+        assert_eq!(read_container.audio_timeline.len(), 1);
+        assert_eq!(read_container.audio_timeline[0].end_time_ms, 2000);
+    }
+
+    #[test]
+    fn test_lazy_container_uses_asset_index() {
+        use crate::LazyVaiContainer;
+
+        let header = VaiHeader::new(64, 64, 30, 1, 2000);
+        let assets = vec![
+            Asset::new(0, 64, 64, vec![1, 2, 3]),
+            Asset::new(1, 32, 32, vec![4, 5, 6, 7]),
+        ];
+        let timeline = vec![
+            TimelineEntry::new(0, 0, 2000, 0, 0, 0),
+            TimelineEntry::new(1, 0, 2000, 10, 10, 1),
+        ];
+        let container = VaiContainer::new(header, assets, timeline);
+
+        let mut buffer = Vec::new();
+        container.write(&mut buffer).unwrap();
+
+        let mut lazy = LazyVaiContainer::open(Cursor::new(buffer)).unwrap();
+        let asset0 = lazy.load_asset(0).unwrap();
+        let asset1 = lazy.load_asset(1).unwrap();
+
+        assert_eq!(asset0.data, vec![1, 2, 3]);
+        assert_eq!(asset1.width, 32);
+        assert_eq!(asset1.data, vec![4, 5, 6, 7]);
     }
 }
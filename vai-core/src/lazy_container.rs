@@ -0,0 +1,168 @@
+//! Lazy, streaming-friendly view over a VAI container
+//!
+//! `VaiContainer::read` loads every asset's bytes into memory up front.
+//! `LazyVaiContainer` instead reads only the header, timeline, and seek index
+//! eagerly (all small) and records a byte-offset table for the assets,
+//! fetching each one's bytes on demand from the underlying `Read + Seek`
+//! source. This lets a demuxer pull only the sprites needed for the frames
+//! it actually composites, instead of slurping the whole file into RAM.
+//!
+//! When the container carries an `aidx` box (every file written by this
+//! version does), the offset table comes straight from it and the `asts`
+//! box itself is never even scanned — `open` costs O(asset count) to read
+//! the index, not O(asset count) *seeks* through the asset section. A file
+//! of the current version that happens to lack an `aidx` box (the box loop
+//! tolerates missing optional boxes) falls back to scanning `asts` directly;
+//! this is unrelated to the `vhdr` version check, which rejects files from
+//! an incompatible format version before any of this runs.
+
+use crate::boxes::{
+    read_full_box_prefix, skip_box_content, BoxHeader, FULL_BOX_PREFIX_LEN, TAG_ASSETS,
+    TAG_ASSET_INDEX, TAG_SEEK_INDEX, TAG_TIMELINE,
+};
+use crate::container::{read_seek_index_box_content, read_timeline_box_content};
+use crate::{Asset, Error, Result, SeekIndexEntry, TimelineEntry, VaiHeader};
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+/// Location of one asset's record within the container file, without its bytes
+#[derive(Debug, Clone, Copy)]
+struct AssetMeta {
+    /// Byte offset of the asset's record, i.e. of its `id` field
+    record_offset: u64,
+}
+
+/// A VAI container whose header, timeline, and seek index are loaded eagerly,
+/// but whose asset bytes are fetched lazily from `reader` on demand
+pub struct LazyVaiContainer<R> {
+    /// Container header
+    pub header: VaiHeader,
+    /// Timeline entries
+    pub timeline: Vec<TimelineEntry>,
+    /// Seek index for random-access scrubbing
+    pub seek_index: Vec<SeekIndexEntry>,
+    asset_metas: std::collections::HashMap<u32, AssetMeta>,
+    reader: R,
+}
+
+impl<R: Read + Seek> LazyVaiContainer<R> {
+    /// Opens a container, reading its header, timeline, seek index, and
+    /// asset offset table but deferring all asset data reads until
+    /// `load_asset` is called
+    ///
+    /// Like `VaiContainer::read`, this loops over boxes until EOF and skips
+    /// any tag it doesn't recognize, so a file carrying boxes this version
+    /// predates still opens cleanly.
+    pub fn open(mut reader: R) -> Result<Self> {
+        let header = VaiHeader::read(&mut reader)?;
+
+        let mut asset_metas = std::collections::HashMap::new();
+        let mut have_asset_index = false;
+        let mut timeline = Vec::new();
+        let mut seek_index = Vec::new();
+
+        while let Some(box_header) = BoxHeader::read(&mut reader)? {
+            match box_header.tag {
+                TAG_ASSET_INDEX => {
+                    let mut content = vec![0u8; box_header.content_len() as usize];
+                    reader.read_exact(&mut content)?;
+                    let mut cursor = Cursor::new(&content[FULL_BOX_PREFIX_LEN as usize..]);
+                    let entries_len = cursor.get_ref().len();
+                    while (cursor.position() as usize) < entries_len {
+                        let id = cursor.read_u32::<LittleEndian>()?;
+                        let record_offset = cursor.read_u64::<LittleEndian>()?;
+                        let _record_len = cursor.read_u32::<LittleEndian>()?;
+                        asset_metas.insert(id, AssetMeta { record_offset });
+                    }
+                    have_asset_index = true;
+                }
+                TAG_ASSETS if have_asset_index => {
+                    // The aidx box (written before asts) already gave us every
+                    // asset's offset, so there's no need to scan this section
+                    // at all — just seek past it.
+                    reader.seek(SeekFrom::Current(box_header.content_len() as i64))?;
+                }
+                TAG_ASSETS => {
+                    // No aidx box ahead of asts: fall back to scanning each
+                    // asset record to discover where it starts.
+                    let content_end = reader.stream_position()? + box_header.content_len();
+                    let (_version, _flags) = read_full_box_prefix(&mut reader)?;
+
+                    while reader.stream_position()? < content_end {
+                        let record_offset = reader.stream_position()?;
+                        let id = reader.read_u32::<LittleEndian>()?;
+                        let _width = reader.read_u32::<LittleEndian>()?;
+                        let _height = reader.read_u32::<LittleEndian>()?;
+                        let data_len = reader.read_u32::<LittleEndian>()?;
+
+                        asset_metas.insert(id, AssetMeta { record_offset });
+
+                        reader.seek(SeekFrom::Current(data_len as i64))?;
+                    }
+                }
+                TAG_TIMELINE => {
+                    let mut content = vec![0u8; box_header.content_len() as usize];
+                    reader.read_exact(&mut content)?;
+                    timeline = read_timeline_box_content(&content[FULL_BOX_PREFIX_LEN as usize..])?;
+                }
+                TAG_SEEK_INDEX => {
+                    let mut content = vec![0u8; box_header.content_len() as usize];
+                    reader.read_exact(&mut content)?;
+                    seek_index =
+                        read_seek_index_box_content(&content[FULL_BOX_PREFIX_LEN as usize..])?;
+                }
+                _ => skip_box_content(&mut reader, box_header.content_len())?,
+            }
+        }
+
+        Ok(Self {
+            header,
+            timeline,
+            seek_index,
+            asset_metas,
+            reader,
+        })
+    }
+
+    /// Loads one asset's bytes on demand, seeking directly to its record
+    /// and reading just that asset's fields and data
+    pub fn load_asset(&mut self, id: u32) -> Result<Asset> {
+        let meta = *self.asset_metas.get(&id).ok_or(Error::AssetNotFound(id))?;
+
+        self.reader.seek(SeekFrom::Start(meta.record_offset))?;
+        let record_id = self.reader.read_u32::<LittleEndian>()?;
+        let width = self.reader.read_u32::<LittleEndian>()?;
+        let height = self.reader.read_u32::<LittleEndian>()?;
+        let data_len = self.reader.read_u32::<LittleEndian>()?;
+        let mut data = vec![0u8; data_len as usize];
+        self.reader.read_exact(&mut data)?;
+
+        if record_id != id {
+            return Err(Error::InvalidAssetId(id));
+        }
+        Ok(Asset::new(id, width, height, data))
+    }
+
+    /// Finds the seek index entry at or before `timestamp_ms`, for O(log n)
+    /// random-access seeking instead of replaying from the start
+    pub fn find_seek_index(&self, timestamp_ms: u64) -> Option<&SeekIndexEntry> {
+        crate::container::find_seek_index_entry(&self.seek_index, timestamp_ms)
+    }
+
+    /// Gets all timeline entries active at a given timestamp, sorted by
+    /// z-order (lower first)
+    pub fn get_active_entries(&self, timestamp_ms: u64) -> Vec<&TimelineEntry> {
+        let mut entries: Vec<&TimelineEntry> = self
+            .timeline
+            .iter()
+            .filter(|e| e.is_active(timestamp_ms))
+            .collect();
+        entries.sort_by_key(|e| e.z_order);
+        entries
+    }
+
+    /// Calculates the frame rate as a float
+    pub fn fps(&self) -> f64 {
+        self.header.fps_num as f64 / self.header.fps_den as f64
+    }
+}
@@ -4,12 +4,19 @@
 //! for the VAI (Video with Artificial Intelligence) video format.
 
 pub mod asset;
+pub mod audio;
+pub(crate) mod boxes;
 pub mod container;
+pub mod lazy_container;
+pub mod seek_index;
 pub mod timeline;
 
 pub use asset::Asset;
+pub use audio::{AudioAsset, AudioTimelineEntry};
 pub use container::{VaiContainer, VaiHeader};
-pub use timeline::TimelineEntry;
+pub use lazy_container::LazyVaiContainer;
+pub use seek_index::{SeekIndexEntry, SEEK_INDEX_INTERVAL_MS};
+pub use timeline::{BlendMode, ReferenceMode, TimelineEntry, Transform, FIXED_POINT_ONE};
 
 /// Result type for vai-core operations
 pub type Result<T> = std::result::Result<T, Error>;
@@ -20,7 +27,7 @@ pub enum Error {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("Invalid magic bytes, expected 'VAI\\0'")]
+    #[error("Missing or invalid 'vhdr' box")]
     InvalidMagic,
 
     #[error("Unsupported version: {0}")]
@@ -1,5 +1,148 @@
 //! Timeline data structures for VAI format
 
+/// What an entry's asset should be composited onto
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceMode {
+    /// Composite relative to the scene background (the stateless, default mode)
+    Background,
+    /// Composite relative to the previously reconstructed frame, so the decoder
+    /// must carry the last rendered frame forward instead of starting fresh
+    Previous,
+}
+
+impl ReferenceMode {
+    /// Decodes a `ReferenceMode` from its on-disk byte representation
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ReferenceMode::Previous,
+            _ => ReferenceMode::Background,
+        }
+    }
+
+    /// Encodes this `ReferenceMode` to its on-disk byte representation
+    pub fn to_u8(self) -> u8 {
+        match self {
+            ReferenceMode::Background => 0,
+            ReferenceMode::Previous => 1,
+        }
+    }
+}
+
+/// Fixed-point value representing `1.0` in a [`Transform`]'s Q16.16 fields
+pub const FIXED_POINT_ONE: i32 = 1 << 16;
+
+/// Per-entry affine transform, stored as Q16.16 fixed-point so the format
+/// stays free of float nondeterminism on disk
+///
+/// Scale and rotation are applied about the asset's own center; `translate_x`
+/// / `translate_y` are a subpixel offset on top of `TimelineEntry`'s integer
+/// `position_x` / `position_y`.
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    /// Horizontal scale factor, Q16.16 (`FIXED_POINT_ONE` = 1.0)
+    pub scale_x: i32,
+    /// Vertical scale factor, Q16.16
+    pub scale_y: i32,
+    /// Rotation in radians, Q16.16
+    pub rotation: i32,
+    /// Subpixel X translation, Q16.16, added to `position_x`
+    pub translate_x: i32,
+    /// Subpixel Y translation, Q16.16, added to `position_y`
+    pub translate_y: i32,
+}
+
+impl Transform {
+    /// The identity transform: no scale, rotation, or extra translation
+    pub fn identity() -> Self {
+        Self {
+            scale_x: FIXED_POINT_ONE,
+            scale_y: FIXED_POINT_ONE,
+            rotation: 0,
+            translate_x: 0,
+            translate_y: 0,
+        }
+    }
+
+    /// Horizontal scale factor as a float
+    pub fn scale_x_f64(&self) -> f64 {
+        self.scale_x as f64 / FIXED_POINT_ONE as f64
+    }
+
+    /// Vertical scale factor as a float
+    pub fn scale_y_f64(&self) -> f64 {
+        self.scale_y as f64 / FIXED_POINT_ONE as f64
+    }
+
+    /// Rotation in radians as a float
+    pub fn rotation_f64(&self) -> f64 {
+        self.rotation as f64 / FIXED_POINT_ONE as f64
+    }
+
+    /// Subpixel X translation as a float
+    pub fn translate_x_f64(&self) -> f64 {
+        self.translate_x as f64 / FIXED_POINT_ONE as f64
+    }
+
+    /// Subpixel Y translation as a float
+    pub fn translate_y_f64(&self) -> f64 {
+        self.translate_y as f64 / FIXED_POINT_ONE as f64
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// How an entry's asset combines with what's already on the canvas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Standard source-over alpha blending
+    Normal,
+    /// Multiplies each channel with the destination, darkening
+    Multiply,
+    /// Inverse-multiplies each channel, lightening
+    Screen,
+    /// Adds each channel to the destination, clamped at full intensity
+    Additive,
+}
+
+impl BlendMode {
+    /// Decodes a `BlendMode` from its on-disk byte representation
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => BlendMode::Multiply,
+            2 => BlendMode::Screen,
+            3 => BlendMode::Additive,
+            _ => BlendMode::Normal,
+        }
+    }
+
+    /// Encodes this `BlendMode` to its on-disk byte representation
+    pub fn to_u8(self) -> u8 {
+        match self {
+            BlendMode::Normal => 0,
+            BlendMode::Multiply => 1,
+            BlendMode::Screen => 2,
+            BlendMode::Additive => 3,
+        }
+    }
+
+    /// Combines one channel's source and destination values, each in
+    /// `0.0..=1.0`, per this blend mode. The result still needs mixing with
+    /// the destination by the source's effective alpha; blend modes only
+    /// replace the "what color" half of compositing, not the "how much".
+    pub fn combine(self, src: f32, dst: f32) -> f32 {
+        match self {
+            BlendMode::Normal => src,
+            BlendMode::Multiply => src * dst,
+            BlendMode::Screen => 1.0 - (1.0 - src) * (1.0 - dst),
+            BlendMode::Additive => (src + dst).min(1.0),
+        }
+    }
+}
+
 /// Represents a single timeline entry that describes when and where an asset appears
 #[derive(Debug, Clone, Copy)]
 pub struct TimelineEntry {
@@ -15,10 +158,19 @@ pub struct TimelineEntry {
     pub position_y: i32,
     /// Layering order (lower = further back; background = 0)
     pub z_order: i32,
+    /// What this entry's asset is composited onto
+    pub reference_mode: ReferenceMode,
+    /// Scale/rotation/subpixel-translation applied to the asset before compositing
+    pub transform: Transform,
+    /// Global opacity (0 = fully transparent, 255 = fully opaque), multiplied
+    /// into the asset's own per-pixel alpha
+    pub opacity: u8,
+    /// How the asset's pixels combine with what's already on the canvas
+    pub blend_mode: BlendMode,
 }
 
 impl TimelineEntry {
-    /// Creates a new timeline entry
+    /// Creates a new timeline entry with the default (background-relative) reference mode
     pub fn new(
         asset_id: u32,
         start_time_ms: u64,
@@ -26,6 +178,57 @@ impl TimelineEntry {
         position_x: i32,
         position_y: i32,
         z_order: i32,
+    ) -> Self {
+        Self::with_reference_mode(
+            asset_id,
+            start_time_ms,
+            end_time_ms,
+            position_x,
+            position_y,
+            z_order,
+            ReferenceMode::Background,
+        )
+    }
+
+    /// Creates a new timeline entry with an explicit reference mode
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_reference_mode(
+        asset_id: u32,
+        start_time_ms: u64,
+        end_time_ms: u64,
+        position_x: i32,
+        position_y: i32,
+        z_order: i32,
+        reference_mode: ReferenceMode,
+    ) -> Self {
+        Self::with_transform(
+            asset_id,
+            start_time_ms,
+            end_time_ms,
+            position_x,
+            position_y,
+            z_order,
+            reference_mode,
+            Transform::identity(),
+            255,
+            BlendMode::Normal,
+        )
+    }
+
+    /// Creates a new timeline entry with an explicit reference mode, transform,
+    /// opacity, and blend mode
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_transform(
+        asset_id: u32,
+        start_time_ms: u64,
+        end_time_ms: u64,
+        position_x: i32,
+        position_y: i32,
+        z_order: i32,
+        reference_mode: ReferenceMode,
+        transform: Transform,
+        opacity: u8,
+        blend_mode: BlendMode,
     ) -> Self {
         Self {
             asset_id,
@@ -34,6 +237,10 @@ impl TimelineEntry {
             position_x,
             position_y,
             z_order,
+            reference_mode,
+            transform,
+            opacity,
+            blend_mode,
         }
     }
 
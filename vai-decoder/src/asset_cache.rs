@@ -0,0 +1,60 @@
+//! A small LRU cache of decoded assets
+//!
+//! VAI containers rarely have more than a few dozen distinct sprites active
+//! at once, so a `Vec` for recency tracking is fine here — no need to reach
+//! for a doubly linked list to get O(1) eviction.
+
+use image::RgbaImage;
+use std::collections::HashMap;
+
+/// Default number of decoded assets to keep cached
+pub const DEFAULT_CAPACITY: usize = 32;
+
+/// LRU cache of decoded (AVIF-to-RGBA) assets, keyed by asset id
+pub struct AssetCache {
+    capacity: usize,
+    entries: HashMap<u32, RgbaImage>,
+    /// Recency order, least recently used first
+    recency: Vec<u32>,
+}
+
+impl AssetCache {
+    /// Creates a cache that holds at most `capacity` decoded assets
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            recency: Vec::new(),
+        }
+    }
+
+    /// Returns the cached image for `id`, marking it most recently used
+    pub fn get(&mut self, id: u32) -> Option<&RgbaImage> {
+        if self.entries.contains_key(&id) {
+            self.touch(id);
+        }
+        self.entries.get(&id)
+    }
+
+    /// Inserts a decoded image, evicting the least recently used entry first
+    /// if the cache is full
+    pub fn insert(&mut self, id: u32, image: RgbaImage) {
+        if !self.entries.contains_key(&id) && self.entries.len() >= self.capacity {
+            self.evict_lru();
+        }
+        self.entries.insert(id, image);
+        self.touch(id);
+    }
+
+    fn touch(&mut self, id: u32) {
+        self.recency.retain(|&cached_id| cached_id != id);
+        self.recency.push(id);
+    }
+
+    fn evict_lru(&mut self) {
+        if !self.recency.is_empty() {
+            let lru_id = self.recency.remove(0);
+            self.entries.remove(&lru_id);
+        }
+    }
+}
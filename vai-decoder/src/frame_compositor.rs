@@ -1,13 +1,19 @@
 //! Frame compositor for blending layers
 
+use crate::asset_cache::{AssetCache, DEFAULT_CAPACITY};
 use crate::{avif_decoder, Error, Result};
 use image::{ImageBuffer, Rgba, RgbaImage};
-use vai_core::VaiContainer;
+use vai_core::{BlendMode, ReferenceMode, Transform, VaiContainer};
 
 /// Frame compositor that can render frames from a VAI container
 pub struct FrameCompositor {
     container: VaiContainer,
-    decoded_assets: std::collections::HashMap<u32, RgbaImage>,
+    decoded_assets: AssetCache,
+    /// The last frame rendered, used as the base for `ReferenceMode::Previous`
+    /// entries. Only meaningful for sequential playback; random-access seeks
+    /// across a `Previous`-mode entry will re-derive from its nearest
+    /// background instead.
+    last_frame: Option<RgbaImage>,
 }
 
 impl FrameCompositor {
@@ -15,14 +21,15 @@ impl FrameCompositor {
     pub fn new(container: VaiContainer) -> Self {
         Self {
             container,
-            decoded_assets: std::collections::HashMap::new(),
+            decoded_assets: AssetCache::new(DEFAULT_CAPACITY),
+            last_frame: None,
         }
     }
 
     /// Decodes and caches an asset
     fn decode_asset(&mut self, asset_id: u32) -> Result<&RgbaImage> {
         // Check if already cached
-        if !self.decoded_assets.contains_key(&asset_id) {
+        if self.decoded_assets.get(asset_id).is_none() {
             // Find the asset
             let asset = self
                 .container
@@ -34,7 +41,7 @@ impl FrameCompositor {
             self.decoded_assets.insert(asset_id, image);
         }
 
-        Ok(self.decoded_assets.get(&asset_id).unwrap())
+        Ok(self.decoded_assets.get(asset_id).unwrap())
     }
 
     /// Renders a frame at the given timestamp
@@ -42,23 +49,60 @@ impl FrameCompositor {
         let width = self.container.header.width;
         let height = self.container.header.height;
 
-        // Create a blank frame
-        let mut frame = ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 255]));
-
         // Get active entries sorted by z_order (collect to avoid borrow issues)
-        let entries: Vec<_> = self.container.get_active_entries(timestamp_ms)
+        let entries: Vec<_> = self
+            .container
+            .get_active_entries(timestamp_ms)
             .into_iter()
-            .map(|e| (e.asset_id, e.position_x, e.position_y))
+            .map(|e| {
+                (
+                    e.asset_id,
+                    e.position_x,
+                    e.position_y,
+                    e.reference_mode,
+                    e.transform,
+                    e.opacity,
+                    e.blend_mode,
+                )
+            })
             .collect();
 
+        // If any entry is relative to the previous frame, start from it
+        // instead of a blank canvas; background-relative entries are already
+        // baked into that prior frame, so they're skipped below.
+        let uses_previous = entries
+            .iter()
+            .any(|&(_, _, _, mode, ..)| mode == ReferenceMode::Previous);
+
+        let mut frame = if uses_previous {
+            self.last_frame
+                .clone()
+                .unwrap_or_else(|| ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 255])))
+        } else {
+            ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 255]))
+        };
+
         // Composite each layer
-        for (asset_id, position_x, position_y) in entries {
+        for (asset_id, position_x, position_y, mode, transform, opacity, blend_mode) in entries {
+            if uses_previous && mode == ReferenceMode::Background {
+                continue;
+            }
+
             let asset_image = self.decode_asset(asset_id)?;
 
             // Overlay the asset at the specified position
-            overlay_image(&mut frame, asset_image, position_x, position_y);
+            overlay_image(
+                &mut frame,
+                asset_image,
+                position_x,
+                position_y,
+                transform,
+                opacity,
+                blend_mode,
+            );
         }
 
+        self.last_frame = Some(frame.clone());
         Ok(frame)
     }
 
@@ -66,48 +110,188 @@ impl FrameCompositor {
     pub fn container(&self) -> &VaiContainer {
         &self.container
     }
+
+    /// Renders the frame at `timestamp_ms`, jumping there directly instead of
+    /// replaying from the start.
+    ///
+    /// Uses the container's seek index to rebuild the `Previous`-mode
+    /// reconstruction state at the nearest indexed timestamp at or before
+    /// `timestamp_ms`, then renders normally from there. Index entries are
+    /// spaced `SEEK_INDEX_INTERVAL_MS` apart, so this lands within one
+    /// interval of the requested timestamp rather than frame-exactly.
+    pub fn seek(&mut self, timestamp_ms: u64) -> Result<RgbaImage> {
+        let indices = self
+            .container
+            .find_seek_index(timestamp_ms)
+            .map(|entry| entry.timeline_indices.clone());
+
+        self.last_frame = match indices {
+            Some(indices) => Some(self.composite_indices(&indices)?),
+            None => None,
+        };
+
+        self.render_frame(timestamp_ms)
+    }
+
+    /// Rebuilds a frame by stamping the given timeline entries, identified by
+    /// index into `container.timeline`, in order onto a blank canvas
+    fn composite_indices(&mut self, indices: &[u32]) -> Result<RgbaImage> {
+        let width = self.container.header.width;
+        let height = self.container.header.height;
+        let mut frame = ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+
+        for &idx in indices {
+            let entry = self.container.timeline[idx as usize];
+            let asset_image = self.decode_asset(entry.asset_id)?;
+            overlay_image(
+                &mut frame,
+                asset_image,
+                entry.position_x,
+                entry.position_y,
+                entry.transform,
+                entry.opacity,
+                entry.blend_mode,
+            );
+        }
+
+        Ok(frame)
+    }
 }
 
-/// Overlays one image onto another at the specified position
-fn overlay_image(base: &mut RgbaImage, overlay: &RgbaImage, x: i32, y: i32) {
+/// Overlays one image onto another at the specified position, honoring the
+/// entry's affine transform, opacity, and blend mode
+///
+/// Scale and rotation are applied about the overlay's own center; `position_x`
+/// / `position_y` plus the transform's subpixel translation then place that
+/// center-relative result onto the base canvas. Destination pixels are
+/// inverse-mapped back into overlay source space and bilinearly sampled, so
+/// scaled-up or rotated edges aren't blocky.
+pub(crate) fn overlay_image(
+    base: &mut RgbaImage,
+    overlay: &RgbaImage,
+    x: i32,
+    y: i32,
+    transform: Transform,
+    opacity: u8,
+    blend_mode: BlendMode,
+) {
+    let scale_x = transform.scale_x_f64();
+    let scale_y = transform.scale_y_f64();
+    if scale_x == 0.0 || scale_y == 0.0 {
+        return; // Degenerate transform: the asset is collapsed to nothing
+    }
+
     let base_width = base.width() as i32;
     let base_height = base.height() as i32;
-    let overlay_width = overlay.width() as i32;
-    let overlay_height = overlay.height() as i32;
+    let overlay_width = overlay.width() as f64;
+    let overlay_height = overlay.height() as f64;
+    let cx = overlay_width / 2.0;
+    let cy = overlay_height / 2.0;
+
+    let theta = transform.rotation_f64();
+    let (sin_t, cos_t) = theta.sin_cos();
+    let translate_x = x as f64 + transform.translate_x_f64();
+    let translate_y = y as f64 + transform.translate_y_f64();
 
-    // Calculate the region to copy
-    let src_x_start = 0.max(-x);
-    let src_y_start = 0.max(-y);
-    let src_x_end = overlay_width.min(base_width - x);
-    let src_y_end = overlay_height.min(base_height - y);
+    // Forward-maps an overlay-local point to base canvas coordinates, used
+    // only to find the destination bounding box below.
+    let forward = |sx: f64, sy: f64| -> (f64, f64) {
+        let dx = (sx - cx) * scale_x;
+        let dy = (sy - cy) * scale_y;
+        (
+            translate_x + cx + dx * cos_t - dy * sin_t,
+            translate_y + cy + dx * sin_t + dy * cos_t,
+        )
+    };
+    let corners = [
+        forward(0.0, 0.0),
+        forward(overlay_width, 0.0),
+        forward(0.0, overlay_height),
+        forward(overlay_width, overlay_height),
+    ];
+    let min_x = corners.iter().map(|c| c.0).fold(f64::MAX, f64::min).floor();
+    let max_x = corners.iter().map(|c| c.0).fold(f64::MIN, f64::max).ceil();
+    let min_y = corners.iter().map(|c| c.1).fold(f64::MAX, f64::min).floor();
+    let max_y = corners.iter().map(|c| c.1).fold(f64::MIN, f64::max).ceil();
 
-    if src_x_start >= src_x_end || src_y_start >= src_y_end {
+    let dest_x_start = (min_x as i32).max(0);
+    let dest_y_start = (min_y as i32).max(0);
+    let dest_x_end = (max_x as i32).min(base_width);
+    let dest_y_end = (max_y as i32).min(base_height);
+
+    if dest_x_start >= dest_x_end || dest_y_start >= dest_y_end {
         return; // Nothing to overlay
     }
 
-    // Copy pixels with alpha blending
-    for src_y in src_y_start..src_y_end {
-        for src_x in src_x_start..src_x_end {
-            let dest_x = (x + src_x) as u32;
-            let dest_y = (y + src_y) as u32;
+    let opacity = opacity as f32 / 255.0;
 
-            if dest_x < base.width() && dest_y < base.height() {
-                let overlay_pixel = overlay.get_pixel(src_x as u32, src_y as u32);
-                let base_pixel = base.get_pixel(dest_x, dest_y);
+    for dest_y in dest_y_start..dest_y_end {
+        for dest_x in dest_x_start..dest_x_end {
+            // Inverse-map this destination pixel back into overlay source
+            // space: undo the translation and center offset, then undo the
+            // rotation (its inverse is the transpose), then undo the scale.
+            let rel_x = dest_x as f64 - translate_x - cx;
+            let rel_y = dest_y as f64 - translate_y - cy;
+            let unrot_x = rel_x * cos_t + rel_y * sin_t;
+            let unrot_y = -rel_x * sin_t + rel_y * cos_t;
+            let src_x = unrot_x / scale_x + cx;
+            let src_y = unrot_y / scale_y + cy;
 
-                // Alpha blending
-                let alpha = overlay_pixel[3] as f32 / 255.0;
-                let inv_alpha = 1.0 - alpha;
+            let Some(sample) = bilinear_sample(overlay, src_x, src_y) else {
+                continue;
+            };
 
-                let blended = Rgba([
-                    (overlay_pixel[0] as f32 * alpha + base_pixel[0] as f32 * inv_alpha) as u8,
-                    (overlay_pixel[1] as f32 * alpha + base_pixel[1] as f32 * inv_alpha) as u8,
-                    (overlay_pixel[2] as f32 * alpha + base_pixel[2] as f32 * inv_alpha) as u8,
-                    255,
-                ]);
+            let base_pixel = *base.get_pixel(dest_x as u32, dest_y as u32);
+            let src_alpha = (sample[3] as f32 / 255.0) * opacity;
+            let inv_alpha = 1.0 - src_alpha;
 
-                base.put_pixel(dest_x, dest_y, blended);
-            }
+            let blend_channel = |c: usize| -> u8 {
+                let src_c = sample[c] as f32 / 255.0;
+                let dst_c = base_pixel[c] as f32 / 255.0;
+                let combined = blend_mode.combine(src_c, dst_c);
+                ((combined * src_alpha + dst_c * inv_alpha) * 255.0).round() as u8
+            };
+
+            base.put_pixel(
+                dest_x as u32,
+                dest_y as u32,
+                Rgba([blend_channel(0), blend_channel(1), blend_channel(2), 255]),
+            );
         }
     }
 }
+
+/// Bilinearly samples `image` at fractional coordinates `(x, y)`, returning
+/// `None` if the point falls outside the image (no extrapolation at edges)
+fn bilinear_sample(image: &RgbaImage, x: f64, y: f64) -> Option<Rgba<u8>> {
+    let width = image.width() as f64;
+    let height = image.height() as f64;
+    if x < 0.0 || y < 0.0 || x >= width || y >= height {
+        return None;
+    }
+
+    let x0 = x.floor() as u32;
+    let y0 = y.floor() as u32;
+    let x1 = (x0 + 1).min(image.width() - 1);
+    let y1 = (y0 + 1).min(image.height() - 1);
+    let fx = x - x0 as f64;
+    let fy = y - y0 as f64;
+
+    let p00 = image.get_pixel(x0, y0);
+    let p10 = image.get_pixel(x1, y0);
+    let p01 = image.get_pixel(x0, y1);
+    let p11 = image.get_pixel(x1, y1);
+
+    let lerp_channel = |c: usize| -> u8 {
+        let top = p00[c] as f64 * (1.0 - fx) + p10[c] as f64 * fx;
+        let bottom = p01[c] as f64 * (1.0 - fx) + p11[c] as f64 * fx;
+        (top * (1.0 - fy) + bottom * fy).round() as u8
+    };
+
+    Some(Rgba([
+        lerp_channel(0),
+        lerp_channel(1),
+        lerp_channel(2),
+        lerp_channel(3),
+    ]))
+}
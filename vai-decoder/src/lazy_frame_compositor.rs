@@ -0,0 +1,147 @@
+//! Frame compositor backed by a `LazyVaiContainer`
+//!
+//! Mirrors `FrameCompositor`'s compositing logic, but pulls asset bytes
+//! on demand from a `Read + Seek` source instead of an already-loaded
+//! `VaiContainer`, so a demuxer only fetches the sprites it actually needs
+//! for the frames it renders.
+
+use crate::asset_cache::{AssetCache, DEFAULT_CAPACITY};
+use crate::frame_compositor::overlay_image;
+use crate::{avif_decoder, Result};
+use image::{ImageBuffer, Rgba, RgbaImage};
+use std::io::{Read, Seek};
+use vai_core::{LazyVaiContainer, ReferenceMode};
+
+/// Frame compositor that lazily fetches asset bytes from `reader` as frames
+/// are rendered, instead of requiring the whole container to be in memory
+pub struct LazyFrameCompositor<R> {
+    container: LazyVaiContainer<R>,
+    decoded_assets: AssetCache,
+    /// The last frame rendered, used as the base for `ReferenceMode::Previous`
+    /// entries; see `FrameCompositor::last_frame`.
+    last_frame: Option<RgbaImage>,
+}
+
+impl<R: Read + Seek> LazyFrameCompositor<R> {
+    /// Creates a new lazy frame compositor over the given container
+    pub fn new(container: LazyVaiContainer<R>) -> Self {
+        Self {
+            container,
+            decoded_assets: AssetCache::new(DEFAULT_CAPACITY),
+            last_frame: None,
+        }
+    }
+
+    /// Gets a reference to the underlying lazy container
+    pub fn container(&self) -> &LazyVaiContainer<R> {
+        &self.container
+    }
+
+    /// Decodes and caches an asset, fetching its bytes from `reader` only on
+    /// a cache miss
+    fn decode_asset(&mut self, asset_id: u32) -> Result<&RgbaImage> {
+        if self.decoded_assets.get(asset_id).is_none() {
+            let asset = self.container.load_asset(asset_id)?;
+            let image = avif_decoder::decode_avif(&asset.data)?;
+            self.decoded_assets.insert(asset_id, image);
+        }
+
+        Ok(self.decoded_assets.get(asset_id).unwrap())
+    }
+
+    /// Renders a frame at the given timestamp
+    pub fn render_frame(&mut self, timestamp_ms: u64) -> Result<RgbaImage> {
+        let width = self.container.header.width;
+        let height = self.container.header.height;
+
+        let entries: Vec<_> = self
+            .container
+            .get_active_entries(timestamp_ms)
+            .into_iter()
+            .map(|e| {
+                (
+                    e.asset_id,
+                    e.position_x,
+                    e.position_y,
+                    e.reference_mode,
+                    e.transform,
+                    e.opacity,
+                    e.blend_mode,
+                )
+            })
+            .collect();
+
+        let uses_previous = entries
+            .iter()
+            .any(|&(_, _, _, mode, ..)| mode == ReferenceMode::Previous);
+
+        let mut frame = if uses_previous {
+            self.last_frame
+                .clone()
+                .unwrap_or_else(|| ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 255])))
+        } else {
+            ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 255]))
+        };
+
+        for (asset_id, position_x, position_y, mode, transform, opacity, blend_mode) in entries {
+            if uses_previous && mode == ReferenceMode::Background {
+                continue;
+            }
+
+            let asset_image = self.decode_asset(asset_id)?;
+            overlay_image(
+                &mut frame,
+                asset_image,
+                position_x,
+                position_y,
+                transform,
+                opacity,
+                blend_mode,
+            );
+        }
+
+        self.last_frame = Some(frame.clone());
+        Ok(frame)
+    }
+
+    /// Renders the frame at `timestamp_ms`, jumping there directly via the
+    /// container's seek index instead of replaying from the start; see
+    /// `FrameCompositor::seek`.
+    pub fn seek(&mut self, timestamp_ms: u64) -> Result<RgbaImage> {
+        let indices = self
+            .container
+            .find_seek_index(timestamp_ms)
+            .map(|entry| entry.timeline_indices.clone());
+
+        self.last_frame = match indices {
+            Some(indices) => Some(self.composite_indices(&indices)?),
+            None => None,
+        };
+
+        self.render_frame(timestamp_ms)
+    }
+
+    /// Rebuilds a frame by stamping the given timeline entries, identified by
+    /// index into `container.timeline`, in order onto a blank canvas
+    fn composite_indices(&mut self, indices: &[u32]) -> Result<RgbaImage> {
+        let width = self.container.header.width;
+        let height = self.container.header.height;
+        let mut frame = ImageBuffer::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+
+        for &idx in indices {
+            let entry = self.container.timeline[idx as usize];
+            let asset_image = self.decode_asset(entry.asset_id)?;
+            overlay_image(
+                &mut frame,
+                asset_image,
+                entry.position_x,
+                entry.position_y,
+                entry.transform,
+                entry.opacity,
+                entry.blend_mode,
+            );
+        }
+
+        Ok(frame)
+    }
+}
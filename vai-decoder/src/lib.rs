@@ -2,10 +2,17 @@
 //!
 //! This library provides functionality to decode VAI video files back into frames.
 
+pub mod asset_cache;
 pub mod avif_decoder;
+pub mod cmaf_exporter;
 pub mod frame_compositor;
+pub mod lazy_frame_compositor;
+pub mod quality;
 
+pub use cmaf_exporter::{export_cmaf, CmafExportOptions};
 pub use frame_compositor::FrameCompositor;
+pub use lazy_frame_compositor::LazyFrameCompositor;
+pub use quality::{compare_frames, psnr, ssim, FrameQuality};
 
 /// Result type for vai-decoder operations
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,195 @@
+//! Full-reference image quality metrics (PSNR, SSIM)
+//!
+//! Used by `vai verify` to compare a decoded VAI frame against the original
+//! source frame it was derived from, so `--quality`/`--threshold` can be
+//! tuned objectively instead of by eye, the way video-AV1 pipelines lean on
+//! VMAF for target-quality tuning.
+
+use image::RgbaImage;
+
+/// SSIM stabilization constant for the luminance term, `(0.01 * 255)^2`
+const SSIM_C1: f64 = 0.01 * 0.01 * 255.0 * 255.0;
+/// SSIM stabilization constant for the contrast/structure term, `(0.03 * 255)^2`
+const SSIM_C2: f64 = 0.03 * 0.03 * 255.0 * 255.0;
+
+/// Side length of the sliding window SSIM is averaged over
+const SSIM_WINDOW: usize = 8;
+
+/// Per-frame quality scores against a reference frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameQuality {
+    /// Peak signal-to-noise ratio in dB over RGB mean squared error.
+    /// `f64::INFINITY` when the frames are pixel-identical.
+    pub psnr: f64,
+    /// Mean structural similarity index over all non-overlapping
+    /// `SSIM_WINDOW x SSIM_WINDOW` windows, in `[-1, 1]` (1 = identical)
+    pub ssim: f64,
+}
+
+/// Computes PSNR and SSIM for `actual` against `reference`.
+///
+/// Both images are compared at `reference`'s resolution; `actual` is
+/// nearest-neighbor resampled first if its dimensions differ (a VAI frame
+/// and its source should already match, but a mismatched `--fps` can shift
+/// frame counts without changing dimensions, so this guards the comparison
+/// rather than panicking on a size mismatch).
+pub fn compare_frames(reference: &RgbaImage, actual: &RgbaImage) -> FrameQuality {
+    let resampled;
+    let actual = if actual.dimensions() == reference.dimensions() {
+        actual
+    } else {
+        resampled = resample_nearest(actual, reference.width(), reference.height());
+        &resampled
+    };
+
+    FrameQuality {
+        psnr: psnr(reference, actual),
+        ssim: ssim(reference, actual),
+    }
+}
+
+/// Peak signal-to-noise ratio in dB, from the mean squared error over the
+/// R, G, and B channels (alpha is not a visible sample, so it's excluded)
+pub fn psnr(reference: &RgbaImage, actual: &RgbaImage) -> f64 {
+    let mse = mean_squared_error(reference, actual);
+    if mse == 0.0 {
+        return f64::INFINITY;
+    }
+    20.0 * 255.0f64.log10() - 10.0 * mse.log10()
+}
+
+fn mean_squared_error(reference: &RgbaImage, actual: &RgbaImage) -> f64 {
+    let width = reference.width().min(actual.width());
+    let height = reference.height().min(actual.height());
+
+    let mut sum_sq = 0.0f64;
+    let mut count = 0u64;
+
+    for y in 0..height {
+        for x in 0..width {
+            let r = reference.get_pixel(x, y);
+            let a = actual.get_pixel(x, y);
+            for c in 0..3 {
+                let d = r[c] as f64 - a[c] as f64;
+                sum_sq += d * d;
+                count += 1;
+            }
+        }
+    }
+
+    if count == 0 {
+        0.0
+    } else {
+        sum_sq / count as f64
+    }
+}
+
+/// Mean SSIM over non-overlapping `SSIM_WINDOW x SSIM_WINDOW` windows of the
+/// luma plane, using the standard luminance/contrast/structure formula:
+/// `((2*mean_r*mean_a + C1) * (2*cov + C2)) / ((mean_r^2 + mean_a^2 + C1) * (var_r + var_a + C2))`
+pub fn ssim(reference: &RgbaImage, actual: &RgbaImage) -> f64 {
+    let width = reference.width().min(actual.width()) as usize;
+    let height = reference.height().min(actual.height()) as usize;
+
+    if width == 0 || height == 0 {
+        return 1.0;
+    }
+
+    let ref_luma = luma_plane(reference, width, height);
+    let act_luma = luma_plane(actual, width, height);
+
+    let mut total = 0.0f64;
+    let mut windows = 0u64;
+
+    let mut wy = 0;
+    while wy < height {
+        let win_h = SSIM_WINDOW.min(height - wy);
+        let mut wx = 0;
+        while wx < width {
+            let win_w = SSIM_WINDOW.min(width - wx);
+            total += window_ssim(&ref_luma, &act_luma, width, wx, wy, win_w, win_h);
+            windows += 1;
+            wx += SSIM_WINDOW;
+        }
+        wy += SSIM_WINDOW;
+    }
+
+    if windows == 0 {
+        1.0
+    } else {
+        total / windows as f64
+    }
+}
+
+fn window_ssim(
+    reference: &[f64],
+    actual: &[f64],
+    stride: usize,
+    x0: usize,
+    y0: usize,
+    win_w: usize,
+    win_h: usize,
+) -> f64 {
+    let n = (win_w * win_h) as f64;
+
+    let mut sum_r = 0.0;
+    let mut sum_a = 0.0;
+    for y in y0..y0 + win_h {
+        for x in x0..x0 + win_w {
+            sum_r += reference[y * stride + x];
+            sum_a += actual[y * stride + x];
+        }
+    }
+    let mean_r = sum_r / n;
+    let mean_a = sum_a / n;
+
+    let mut var_r = 0.0;
+    let mut var_a = 0.0;
+    let mut cov = 0.0;
+    for y in y0..y0 + win_h {
+        for x in x0..x0 + win_w {
+            let dr = reference[y * stride + x] - mean_r;
+            let da = actual[y * stride + x] - mean_a;
+            var_r += dr * dr;
+            var_a += da * da;
+            cov += dr * da;
+        }
+    }
+    var_r /= n;
+    var_a /= n;
+    cov /= n;
+
+    let numerator = (2.0 * mean_r * mean_a + SSIM_C1) * (2.0 * cov + SSIM_C2);
+    let denominator = (mean_r * mean_r + mean_a * mean_a + SSIM_C1) * (var_r + var_a + SSIM_C2);
+
+    numerator / denominator
+}
+
+/// Rec. 601 luma plane, row-major, clipped to `width x height`
+fn luma_plane(image: &RgbaImage, width: usize, height: usize) -> Vec<f64> {
+    let mut plane = Vec::with_capacity(width * height);
+    for y in 0..height as u32 {
+        for x in 0..width as u32 {
+            let p = image.get_pixel(x, y);
+            let luma = 0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64;
+            plane.push(luma);
+        }
+    }
+    plane
+}
+
+/// Nearest-neighbor resample of `src` to `dst_width x dst_height`
+fn resample_nearest(src: &RgbaImage, dst_width: u32, dst_height: u32) -> RgbaImage {
+    let mut out = RgbaImage::new(dst_width, dst_height);
+    let (src_width, src_height) = (src.width().max(1), src.height().max(1));
+
+    for y in 0..dst_height {
+        let sy = (y as u64 * src_height as u64 / dst_height.max(1) as u64) as u32;
+        for x in 0..dst_width {
+            let sx = (x as u64 * src_width as u64 / dst_width.max(1) as u64) as u32;
+            out.put_pixel(x, y, *src.get_pixel(sx.min(src_width - 1), sy.min(src_height - 1)));
+        }
+    }
+
+    out
+}
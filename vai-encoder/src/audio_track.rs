@@ -0,0 +1,41 @@
+//! Audio track extraction for the optional VAI audio section
+//!
+//! Pulls the best audio stream out of a [`VideoReader`] as a single
+//! concatenated PCM blob spanning the whole video, the same way
+//! `cmaf_exporter` stands in PNG frames for a real video codec until one is
+//! wired in: this stores resampled PCM rather than a real codec's bitstream
+//! (there's no Opus encoder linked in here), so the box format and CLI
+//! plumbing can be exercised end-to-end with a payload any reader can
+//! already decode.
+
+use crate::video_reader::AudioResampleOptions;
+use crate::{Result, VideoReader};
+use vai_core::{AudioAsset, AudioTimelineEntry};
+
+/// Audio asset ID used for the single extracted track
+const AUDIO_ASSET_ID: u32 = 0;
+
+/// Extracts `reader`'s best audio stream as one [`AudioAsset`] holding its
+/// entire resampled PCM, paired with an [`AudioTimelineEntry`] spanning the
+/// full video duration. Returns `Ok(None)` if the source has no audio
+/// stream to extract.
+pub fn extract_audio_track(
+    reader: &mut VideoReader,
+    options: AudioResampleOptions,
+) -> Result<Option<(AudioAsset, AudioTimelineEntry)>> {
+    if !reader.has_audio_stream() {
+        return Ok(None);
+    }
+
+    let mut pcm = Vec::new();
+    reader.read_audio_samples_streaming(options, |_pts_ms, chunk| {
+        pcm.extend_from_slice(chunk);
+        Ok(())
+    })?;
+
+    let channels = options.channel_layout.channels() as u8;
+    let asset = AudioAsset::new(AUDIO_ASSET_ID, options.sample_rate, channels, pcm);
+    let entry = AudioTimelineEntry::new(AUDIO_ASSET_ID, 0, reader.duration_ms());
+
+    Ok(Some((asset, entry)))
+}
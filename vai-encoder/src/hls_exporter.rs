@@ -0,0 +1,204 @@
+//! Fragmented-MP4 / HLS export for composited VAI playback
+//!
+//! Pairs with `VideoWriter` the same way `VideoWriter` pairs with
+//! `VideoReader`: instead of muxing frames read from a source video, this
+//! walks a VAI timeline through `vai_decoder::FrameCompositor` and muxes the
+//! composited RGBA frames straight to FFmpeg's own `hls` output format,
+//! which writes an `init.mp4` plus numbered fragmented-MP4 media segments
+//! and a `.m3u8` VOD playlist — the same fMP4/HLS layout a dedicated Rust
+//! muxer crate would produce, but delegated to libavformat instead of
+//! hand-rolled box writers.
+//!
+//! Segment boundaries default to a fixed duration, but `split_on_scene_boundaries`
+//! instead forces a keyframe (and therefore a new segment) at every
+//! scene-background cut recorded in the container's timeline, so no segment
+//! straddles a scene change.
+
+use crate::video_writer::VideoCodec;
+use crate::{Error, Result};
+use std::path::Path;
+use vai_decoder::FrameCompositor;
+
+/// Options controlling segmenting, bitrate, and codec for an HLS export
+#[derive(Debug, Clone, Copy)]
+pub struct HlsExportOptions {
+    pub codec: VideoCodec,
+    /// Target segment duration, in milliseconds, when not splitting on scene
+    /// boundaries
+    pub segment_duration_ms: u64,
+    /// Target bitrate, in bits/sec, passed to the FFmpeg encoder
+    pub bitrate: usize,
+    /// Force a new segment at every scene-background cut (every timeline
+    /// entry with `z_order == 0`) instead of at fixed `segment_duration_ms`
+    /// intervals
+    pub split_on_scene_boundaries: bool,
+}
+
+impl Default for HlsExportOptions {
+    fn default() -> Self {
+        Self {
+            codec: VideoCodec::H264,
+            segment_duration_ms: 4000,
+            bitrate: 2_000_000,
+            split_on_scene_boundaries: false,
+        }
+    }
+}
+
+/// Exports `compositor`'s timeline as an HLS VOD asset into `output_dir`:
+/// `init.mp4`, `segment_%03d.m4s`, and `stream.m3u8`.
+pub fn export_hls(
+    compositor: &mut FrameCompositor,
+    output_dir: &Path,
+    options: &HlsExportOptions,
+) -> Result<()> {
+    ffmpeg_next::init()?;
+    std::fs::create_dir_all(output_dir)?;
+
+    let header = compositor.container().header.clone();
+    let width = (header.width + 1) & !1;
+    let height = (header.height + 1) & !1;
+    let fps = header.fps_num as f64 / header.fps_den.max(1) as f64;
+    let time_base = ffmpeg_next::Rational(header.fps_den.max(1) as i32, header.fps_num as i32);
+
+    let playlist_path = output_dir.join("stream.m3u8");
+    let mut hls_opts = ffmpeg_next::Dictionary::new();
+    hls_opts.set("hls_segment_type", "fmp4");
+    hls_opts.set("hls_fmp4_init_filename", "init.mp4");
+    hls_opts.set("hls_segment_filename", "segment_%03d.m4s");
+    hls_opts.set("hls_playlist_type", "vod");
+    hls_opts.set("hls_flags", "independent_segments");
+
+    if options.split_on_scene_boundaries {
+        // Force a keyframe (and therefore a new segment) at every
+        // scene-background cut; `hls_time` only needs to be small enough
+        // that no interval between cuts is missed.
+        let cut_times = scene_boundary_seconds(compositor);
+        hls_opts.set("force_key_frames", &cut_times.join(","));
+        hls_opts.set("hls_time", "1");
+    } else {
+        hls_opts.set(
+            "hls_time",
+            &(options.segment_duration_ms as f64 / 1000.0).to_string(),
+        );
+    }
+
+    let mut octx = ffmpeg_next::format::output_as(&playlist_path, "hls")?;
+
+    let codec =
+        ffmpeg_next::encoder::find_by_name(options.codec.encoder_name()).ok_or_else(|| {
+            Error::VideoEncode(format!(
+                "No encoder found for {}",
+                options.codec.encoder_name()
+            ))
+        })?;
+
+    let mut stream = octx.add_stream(codec)?;
+    let stream_index = stream.index();
+
+    let context = ffmpeg_next::codec::context::Context::from_parameters(
+        ffmpeg_next::codec::Parameters::new(),
+    )?;
+    let mut video = context.encoder().video()?;
+    video.set_width(width);
+    video.set_height(height);
+    video.set_format(ffmpeg_next::format::Pixel::YUV420P);
+    video.set_time_base(time_base);
+    video.set_frame_rate(Some(ffmpeg_next::Rational(
+        header.fps_num as i32,
+        header.fps_den.max(1) as i32,
+    )));
+    video.set_bit_rate(options.bitrate);
+
+    if octx
+        .format()
+        .flags()
+        .contains(ffmpeg_next::format::Flags::GLOBAL_HEADER)
+    {
+        video.set_flags(ffmpeg_next::codec::Flags::GLOBAL_HEADER);
+    }
+
+    let mut encoder = video.open_as(codec)?;
+    stream.set_parameters(&encoder);
+    stream.set_time_base(time_base);
+
+    // The hls_* options are the muxer's own, so they go to the header write
+    // (which hands them to the muxer), not to the output file open.
+    octx.write_header_with(hls_opts)?;
+
+    let mut scaler = ffmpeg_next::software::scaling::Context::get(
+        ffmpeg_next::format::Pixel::RGBA,
+        width,
+        height,
+        ffmpeg_next::format::Pixel::YUV420P,
+        width,
+        height,
+        ffmpeg_next::software::scaling::Flags::BILINEAR,
+    )?;
+
+    let ms_per_frame = 1000.0 / fps;
+    let frame_count = ((header.duration_ms as f64 / ms_per_frame).round() as usize).max(1);
+
+    let mut yuv_frame = ffmpeg_next::frame::Video::empty();
+    let mut packet = ffmpeg_next::Packet::empty();
+
+    for frame_idx in 0..frame_count {
+        let timestamp_ms = (frame_idx as f64 * ms_per_frame) as u64;
+        let frame = compositor.render_frame(timestamp_ms)?;
+
+        let mut rgba_frame =
+            ffmpeg_next::frame::Video::new(ffmpeg_next::format::Pixel::RGBA, width, height);
+        {
+            let stride = rgba_frame.stride(0);
+            let dst = rgba_frame.data_mut(0);
+            let src = frame.as_raw();
+            let row_bytes = (frame.width() as usize) * 4;
+            for y in 0..frame.height() as usize {
+                let src_off = y * row_bytes;
+                let dst_off = y * stride;
+                dst[dst_off..dst_off + row_bytes]
+                    .copy_from_slice(&src[src_off..src_off + row_bytes]);
+            }
+        }
+
+        scaler.run(&rgba_frame, &mut yuv_frame)?;
+        yuv_frame.set_pts(Some(frame_idx as i64));
+
+        encoder.send_frame(&yuv_frame)?;
+        while encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(stream_index);
+            packet.rescale_ts(time_base, octx.stream(stream_index).unwrap().time_base());
+            packet.write_interleaved(&mut octx)?;
+        }
+    }
+
+    encoder.send_eof()?;
+    while encoder.receive_packet(&mut packet).is_ok() {
+        packet.set_stream(stream_index);
+        packet.rescale_ts(time_base, octx.stream(stream_index).unwrap().time_base());
+        packet.write_interleaved(&mut octx)?;
+    }
+
+    octx.write_trailer()?;
+
+    Ok(())
+}
+
+/// Collects every scene-background cut in `compositor`'s timeline (every
+/// entry with `z_order == 0`, deduplicated and sorted) as seconds-formatted
+/// strings, for FFmpeg's `force_key_frames` option.
+fn scene_boundary_seconds(compositor: &FrameCompositor) -> Vec<String> {
+    let mut cuts: Vec<u64> = compositor
+        .container()
+        .timeline
+        .iter()
+        .filter(|e| e.z_order == 0)
+        .map(|e| e.start_time_ms)
+        .collect();
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    cuts.into_iter()
+        .map(|ms| format!("{:.3}", ms as f64 / 1000.0))
+        .collect()
+}
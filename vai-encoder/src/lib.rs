@@ -2,12 +2,22 @@
 //!
 //! This library provides functionality to encode video files into VAI format.
 
+pub mod audio_track;
 pub mod avif_encoder;
+pub mod ffmpeg_encoder;
+pub mod hls_exporter;
+pub mod progress_tracker;
 pub mod scene_analyzer;
+pub mod scene_detector;
 pub mod video_reader;
+pub mod video_writer;
 
+pub use audio_track::extract_audio_track;
+pub use hls_exporter::{export_hls, HlsExportOptions};
 pub use scene_analyzer::SceneAnalyzer;
-pub use video_reader::VideoReader;
+pub use scene_detector::{detect_scenes, SceneDetectorConfig, SceneSegment};
+pub use video_reader::{AudioResampleOptions, VideoReader};
+pub use video_writer::{VideoCodec, VideoWriter, VideoWriterOptions};
 
 /// Result type for vai-encoder operations
 pub type Result<T> = std::result::Result<T, Error>;
@@ -30,11 +40,17 @@ pub enum Error {
     #[error("AVIF encode error: {0}")]
     AvifEncode(String),
 
+    #[error("Video encode error: {0}")]
+    VideoEncode(String),
+
     #[error("Invalid video file")]
     InvalidVideo,
 
     #[error("No video stream found")]
     NoVideoStream,
+
+    #[error("No audio stream found")]
+    NoAudioStream,
 }
 
 /// Encoder configuration
@@ -48,6 +64,27 @@ pub struct EncoderConfig {
     pub threshold: u8,
     /// Minimum region size in pixels
     pub min_region_size: u32,
+    /// Maximum gap in pixels between two components' bounding boxes for them
+    /// to be merged into a single region
+    pub merge_gap: u32,
+    /// Diff each frame against the previously reconstructed frame instead of
+    /// the segment background, emitting `ReferenceMode::Previous` regions.
+    /// Better for smooth motion (pans, cursor trails); costs decode-side state.
+    pub temporal_reference: bool,
+    /// Prefer FFmpeg's AV1 encoder for AVIF assets (falling back to `ravif`
+    /// if it's unavailable) instead of always using `ravif`. See
+    /// `avif_encoder::encode_avif_auto`.
+    pub use_ffmpeg_avif: bool,
+    /// Number of recent `scene_analyzer::SceneAnalyzer::detect_cuts` SAD
+    /// scores kept for the rolling mean/stddev cut threshold
+    pub scene_window: usize,
+    /// Multiplier (k) applied to the stddev of recent SAD scores, added to
+    /// their mean, to flag a scene cut
+    pub scene_adaptive_factor: f64,
+    /// Minimum number of frames a scene must span before another cut can be flagged
+    pub min_scene_len_frames: usize,
+    /// Maximum number of frames a scene may span before a cut is forced
+    pub max_scene_len: usize,
 }
 
 impl Default for EncoderConfig {
@@ -57,6 +94,13 @@ impl Default for EncoderConfig {
             fps: None,
             threshold: 30,
             min_region_size: 64,
+            merge_gap: 8,
+            temporal_reference: false,
+            use_ffmpeg_avif: false,
+            scene_window: 20,
+            scene_adaptive_factor: 3.0,
+            min_scene_len_frames: 8,
+            max_scene_len: 300,
         }
     }
 }
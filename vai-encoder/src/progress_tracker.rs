@@ -1,15 +1,36 @@
 //! Progress tracking with ETA estimation
 
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-/// Thread-safe progress tracker with ETA estimation
+/// Number of recent `(time, count)` samples kept for the windowed rate estimate.
+const WINDOW_SIZE: usize = 20;
+
+/// A point-in-time snapshot of a `ProgressTracker`'s state, for callers that
+/// want to render their own progress UI instead of the built-in `println!`.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressSnapshot {
+    pub processed: u64,
+    pub total: u64,
+    pub percent: f64,
+    /// Samples/sec estimated from the sliding window, not the cumulative average.
+    pub instantaneous_rate: f64,
+    /// `None` once `processed >= total`, or while nothing has been processed
+    /// yet. Before the window holds two distinct samples, the rate (and so
+    /// the ETA) comes from the cumulative average instead.
+    pub eta_secs: Option<f64>,
+}
+
+/// Thread-safe progress tracker with sliding-window ETA estimation
 pub struct ProgressTracker {
     total: u64,
     processed: Arc<AtomicU64>,
     start_time: Instant,
     label: String,
+    // (timestamp, processed count at that timestamp), oldest first.
+    window: Mutex<VecDeque<(Instant, u64)>>,
 }
 
 impl ProgressTracker {
@@ -20,6 +41,7 @@ impl ProgressTracker {
             processed: Arc::new(AtomicU64::new(0)),
             start_time: Instant::now(),
             label: label.to_string(),
+            window: Mutex::new(VecDeque::with_capacity(WINDOW_SIZE)),
         }
     }
 
@@ -32,31 +54,84 @@ impl ProgressTracker {
     pub fn increment_and_report(&self, report_interval: u64) {
         let current = self.processed.fetch_add(1, Ordering::Relaxed) + 1;
         if current % report_interval == 0 || current == self.total {
+            self.record_sample(current);
             self.print_progress(current);
         }
     }
 
-    /// Prints current progress with ETA
-    fn print_progress(&self, current: u64) {
-        let elapsed = self.start_time.elapsed();
-        let elapsed_secs = elapsed.as_secs_f64();
+    /// Records `current` into the sliding window, dropping the oldest sample
+    /// once the window is full.
+    fn record_sample(&self, current: u64) {
+        let mut window = self.window.lock().unwrap();
+        window.push_back((Instant::now(), current));
+        if window.len() > WINDOW_SIZE {
+            window.pop_front();
+        }
+    }
 
+    /// Returns a machine-readable snapshot of current progress. The rate is
+    /// estimated from the sliding window (oldest vs. newest recorded sample)
+    /// rather than the cumulative average, so it tracks recent throughput
+    /// instead of being skewed by a slow start or a slow phase partway through.
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        let current = self.processed.load(Ordering::Relaxed);
         let percent = if self.total > 0 {
             (current as f64 / self.total as f64) * 100.0
         } else {
             0.0
         };
 
+        let window = self.window.lock().unwrap();
+        let instantaneous_rate = match (window.front(), window.back()) {
+            (Some(&(oldest_time, oldest_count)), Some(&(newest_time, newest_count)))
+                if newest_time > oldest_time && newest_count > oldest_count =>
+            {
+                let elapsed = (newest_time - oldest_time).as_secs_f64();
+                (newest_count - oldest_count) as f64 / elapsed
+            }
+            _ => {
+                // Not enough samples yet; fall back to the cumulative average.
+                let elapsed = self.start_time.elapsed().as_secs_f64();
+                if elapsed > 0.0 {
+                    current as f64 / elapsed
+                } else {
+                    0.0
+                }
+            }
+        };
+        drop(window);
+
+        let eta_secs = if current > 0 && current < self.total && instantaneous_rate > 0.0 {
+            Some((self.total - current) as f64 / instantaneous_rate)
+        } else {
+            None
+        };
+
+        ProgressSnapshot {
+            processed: current,
+            total: self.total,
+            percent,
+            instantaneous_rate,
+            eta_secs,
+        }
+    }
+
+    /// Prints current progress with ETA, using the windowed rate estimate.
+    fn print_progress(&self, current: u64) {
+        let snapshot = self.snapshot();
+        let elapsed_secs = self.start_time.elapsed().as_secs_f64();
+
         if current > 0 && current < self.total {
-            let rate = current as f64 / elapsed_secs;
-            let remaining = (self.total - current) as f64 / rate;
-            let eta = format_duration(remaining);
+            let eta = snapshot
+                .eta_secs
+                .map(format_duration)
+                .unwrap_or_else(|| "unknown".to_string());
             println!(
                 "  {} {}/{} ({:.1}%) - elapsed: {} - ETA: {}",
                 self.label,
                 current,
                 self.total,
-                percent,
+                snapshot.percent,
                 format_duration(elapsed_secs),
                 eta,
             );
@@ -87,4 +162,4 @@ fn format_duration(secs: f64) -> String {
         let remaining_secs = remaining - (mins as f64 * 60.0);
         format!("{}h {}m {:.0}s", hours, mins, remaining_secs)
     }
-}
\ No newline at end of file
+}
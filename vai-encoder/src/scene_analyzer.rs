@@ -1,10 +1,31 @@
 //! Scene analysis and motion detection
 
-use crate::scene_detector::SceneSegment;
-use crate::{avif_encoder, progress_tracker::ProgressTracker, EncoderConfig, Result};
+use crate::scene_detector::{sample_median_background, SceneSegment};
+use crate::{avif_encoder, progress_tracker::ProgressTracker, EncoderConfig, Result, VideoReader};
 use image::{ImageBuffer, Rgba, RgbaImage};
-use std::thread;
-use vai_core::{Asset, TimelineEntry, VaiContainer, VaiHeader};
+use rayon::prelude::*;
+use std::collections::VecDeque;
+use vai_core::{Asset, ReferenceMode, TimelineEntry, VaiContainer, VaiHeader};
+
+/// Side length of the fixed luma grid `detect_cuts` diffs frame-to-frame
+const SAD_GRID_SIZE: usize = 32;
+
+/// Minimum grid SAD below which a spike is never treated as a cut, even if
+/// it exceeds `mean + k * stddev` — guards against the adaptive threshold
+/// collapsing to near zero on very static footage
+const SAD_ABSOLUTE_FLOOR: f64 = 12.0;
+
+/// Number of frames over which a sustained monotonic luma-mean drift is
+/// treated as a fade
+const FADE_WINDOW: usize = 10;
+
+/// Minimum total luma-mean drift (0-255 scale) over `FADE_WINDOW` frames to
+/// flag a fade cut
+const FADE_LUMA_DELTA: f64 = 40.0;
+
+/// Number of frames sampled per segment to build its temporal-median
+/// background
+const BACKGROUND_SAMPLES: usize = 5;
 
 /// Scene analyzer that extracts background and motion regions
 pub struct SceneAnalyzer {
@@ -17,6 +38,120 @@ impl SceneAnalyzer {
         Self { config }
     }
 
+    /// Detects scene cuts with a content-adaptive SAD detector, as pass 1
+    /// ahead of `analyze_parallel`.
+    ///
+    /// Each frame is downscaled to a fixed `SAD_GRID_SIZE × SAD_GRID_SIZE`
+    /// luma grid and diffed against the previous frame's grid by sum of
+    /// absolute differences. A hard cut is flagged once `min_scene_len_frames`
+    /// frames have elapsed since the last cut and the SAD exceeds both
+    /// `SAD_ABSOLUTE_FLOOR` and `mean + scene_adaptive_factor * stddev` of the
+    /// last `scene_window` SAD values. A fade cut is flagged independently of
+    /// the SAD threshold when the frame's mean luma has drifted monotonically
+    /// by at least `FADE_LUMA_DELTA` over the last `FADE_WINDOW` frames (a
+    /// slow fade raises little SAD per frame, so it would otherwise never
+    /// cross the adaptive threshold). Either condition, or the segment
+    /// reaching `max_scene_len` frames, ends the current segment.
+    ///
+    /// Segment backgrounds are then the per-pixel temporal median of
+    /// `BACKGROUND_SAMPLES` frames sampled across each segment, via
+    /// `scene_detector::sample_median_background` (`scene_detector::detect_scenes`
+    /// instead uses each segment's first frame).
+    pub fn detect_cuts(&self, reader: &mut VideoReader) -> Result<Vec<SceneSegment>> {
+        let window = self.config.scene_window.max(1);
+
+        let mut boundaries: Vec<usize> = vec![0];
+        let mut prev_luma: Option<Vec<u8>> = None;
+        let mut recent_sads: VecDeque<f64> = VecDeque::with_capacity(window);
+        let mut luma_means: VecDeque<f64> = VecDeque::with_capacity(FADE_WINDOW);
+        // Frames in the current scene so far, counting the frame that opened it.
+        let mut frames_since_cut: usize = 1;
+        let mut total_frames: usize = 0;
+
+        reader.read_frames_streaming(|frame_idx, _pts_ms, frame| {
+            total_frames = frame_idx + 1;
+            let luma = downscale_luma(&frame, SAD_GRID_SIZE);
+            let luma_mean =
+                luma.iter().map(|&v| v as f64).sum::<f64>() / luma.len().max(1) as f64;
+
+            if let Some(ref prev) = prev_luma {
+                let sad = sum_abs_diff(prev, &luma);
+
+                let (mean, stddev) = mean_and_stddev(&recent_sads);
+                let forced_cut = frames_since_cut >= self.config.max_scene_len;
+                let min_len_ok = frames_since_cut >= self.config.min_scene_len_frames;
+
+                let hard_cut = min_len_ok
+                    && sad > SAD_ABSOLUTE_FLOOR
+                    && sad > mean + self.config.scene_adaptive_factor * stddev;
+                let fade_cut = min_len_ok && is_monotonic_fade(&luma_means, luma_mean);
+
+                if forced_cut || hard_cut || fade_cut {
+                    boundaries.push(frame_idx);
+                    frames_since_cut = 1;
+                    recent_sads.clear();
+                    luma_means.clear();
+                } else {
+                    frames_since_cut += 1;
+                }
+
+                recent_sads.push_back(sad);
+                if recent_sads.len() > window {
+                    recent_sads.pop_front();
+                }
+            }
+
+            luma_means.push_back(luma_mean);
+            if luma_means.len() > FADE_WINDOW {
+                luma_means.pop_front();
+            }
+
+            prev_luma = Some(luma);
+
+            if (frame_idx + 1) % 200 == 0 {
+                println!(
+                    "  Scene detection: scanned {} frames, {} scenes so far",
+                    frame_idx + 1,
+                    boundaries.len()
+                );
+            }
+
+            Ok(())
+        })?;
+
+        if total_frames == 0 {
+            return Ok(Vec::new());
+        }
+
+        let ms_per_frame = {
+            let (fps_num, fps_den) = reader.frame_rate();
+            if fps_num > 0 {
+                1000.0 * fps_den as f64 / fps_num as f64
+            } else {
+                0.0
+            }
+        };
+
+        let mut segments = Vec::with_capacity(boundaries.len());
+        for (i, &start) in boundaries.iter().enumerate() {
+            let end = boundaries.get(i + 1).copied().unwrap_or(total_frames);
+            let background =
+                sample_median_background(reader, start, end, BACKGROUND_SAMPLES, ms_per_frame)?;
+
+            segments.push(SceneSegment {
+                start_frame: start,
+                end_frame: if i + 1 < boundaries.len() {
+                    end
+                } else {
+                    total_frames
+                },
+                background,
+            });
+        }
+
+        Ok(segments)
+    }
+
     /// Analyzes frames and creates a VAI container (legacy, loads all frames into memory)
     pub fn analyze(
         &self,
@@ -78,15 +213,7 @@ impl SceneAnalyzer {
             }
         }
 
-        let header = VaiHeader::new(
-            width,
-            height,
-            fps_num,
-            fps_den,
-            duration_ms,
-            assets.len() as u32,
-            timeline.len() as u32,
-        );
+        let header = VaiHeader::new(width, height, fps_num, fps_den, duration_ms);
 
         Ok(VaiContainer::new(header, assets, timeline))
     }
@@ -95,6 +222,14 @@ impl SceneAnalyzer {
     /// The first frame is used as the background. Subsequent frames are compared
     /// against it and only the diff regions are kept. This uses O(1) frame memory
     /// instead of O(N).
+    ///
+    /// When `config.temporal_reference` is enabled, frames are instead diffed
+    /// against the last *reconstructed* frame (background plus every region
+    /// applied so far) rather than the raw background, and the resulting
+    /// regions are tagged `ReferenceMode::Previous`. This keeps slow, smoothly
+    /// moving content (a pan, a cursor trail) to a small delta per frame
+    /// instead of a fresh full-size region every time it drifts off the
+    /// original background.
     pub fn analyze_streaming(
         &self,
         reader: &mut crate::VideoReader,
@@ -105,6 +240,7 @@ impl SceneAnalyzer {
         duration_ms: u64,
     ) -> Result<VaiContainer> {
         let mut background: Option<RgbaImage> = None;
+        let mut reconstructed: Option<RgbaImage> = None;
         let mut assets: Vec<Asset> = Vec::new();
         let mut timeline: Vec<TimelineEntry> = Vec::new();
         let mut asset_id: u32 = 1;
@@ -123,7 +259,7 @@ impl SceneAnalyzer {
 
         let progress = ProgressTracker::new(estimated_frame_count, "Encoding frames:");
 
-        reader.read_frames_streaming(|frame_idx, frame| {
+        reader.read_frames_streaming(|frame_idx, _pts_ms, frame| {
             total_frames = frame_idx + 1;
 
             if frame_idx == 0 {
@@ -131,9 +267,18 @@ impl SceneAnalyzer {
                 let background_asset = Asset::new(0, width, height, background_data);
                 assets.push(background_asset);
                 timeline.push(TimelineEntry::new(0, 0, duration_ms, 0, 0, 0));
+                if config.temporal_reference {
+                    reconstructed = Some(frame.clone());
+                }
                 background = Some(frame);
             } else if let Some(ref bg) = background {
-                let diff_regions = find_diff_regions(&config, bg, &frame);
+                let mode = if config.temporal_reference {
+                    ReferenceMode::Previous
+                } else {
+                    ReferenceMode::Background
+                };
+                let diff_target = reconstructed.as_ref().unwrap_or(bg);
+                let diff_regions = find_diff_regions(&config, diff_target, &frame);
 
                 for (x, y, region_img) in diff_regions {
                     let region_data = avif_encoder::encode_avif(&region_img, quality)?;
@@ -148,15 +293,14 @@ impl SceneAnalyzer {
                     let start_time = (frame_idx as u64) * ms_per_frame;
                     let end_time = start_time + ms_per_frame;
 
-                    timeline.push(TimelineEntry::new(
-                        asset_id,
-                        start_time,
-                        end_time,
-                        x as i32,
-                        y as i32,
-                        1,
+                    timeline.push(TimelineEntry::with_reference_mode(
+                        asset_id, start_time, end_time, x as i32, y as i32, 1, mode,
                     ));
 
+                    if let Some(ref mut recon) = reconstructed {
+                        stamp_region(recon, &region_img, x, y);
+                    }
+
                     asset_id += 1;
                 }
             }
@@ -173,32 +317,34 @@ impl SceneAnalyzer {
             timeline.len()
         );
 
-        let header = VaiHeader::new(
-            width,
-            height,
-            fps_num,
-            fps_den,
-            duration_ms,
-            assets.len() as u32,
-            timeline.len() as u32,
-        );
+        let header = VaiHeader::new(width, height, fps_num, fps_den, duration_ms);
 
         Ok(VaiContainer::new(header, assets, timeline))
     }
 
-    /// Two-pass chunked parallel encoding:
+    /// Two-pass parallel encoding:
     ///
-    /// **Pass 1** – Scene detection (already done by caller via `scene_detector`).
-    /// **Pass 2** – Read frames a second time. Raw frames are buffered in
-    ///   chunks of up to `CHUNK_SIZE`. Each time the buffer fills (or a segment
-    ///   boundary / end-of-stream is reached) the chunk is encoded in parallel
-    ///   across all CPU cores, only the compact AVIF results are kept, and the
-    ///   raw frames are freed.  This bounds peak memory to roughly
-    ///   `CHUNK_SIZE × frame_size` plus the (much smaller) accumulated AVIF
-    ///   assets, and needs no temporary files on disk.
+    /// **Pass 1** – Scene detection (already done by caller via
+    ///   `detect_cuts`, which also clamps the trailing segment's `end_frame`
+    ///   to the real frame count).
+    /// **Pass 2** – Each `SceneSegment` is encoded concurrently on a `rayon`
+    ///   pool sized to `num_cpus::get()`: an independent `VideoReader` is
+    ///   opened on `source_path` per segment (so segments decode without
+    ///   contending over one shared decoder), its frames are diffed against
+    ///   `seg.background` the same way `analyze_streaming` diffs against the
+    ///   single video-wide background, and every background/region is
+    ///   AVIF-encoded via `avif_encoder::encode_avif_auto`.
+    ///
+    /// Segment results are collected into a `Vec` indexed by their position
+    /// in `segments`, which `rayon`'s `par_iter().map().collect()` always
+    /// preserves regardless of which segment's thread finishes first, so the
+    /// asset IDs and timeline assigned below – and therefore the resulting
+    /// container's bytes – are identical across runs no matter how the
+    /// thread pool schedules the work. Progress is reported once per
+    /// completed segment via the shared `ProgressTracker`.
     pub fn analyze_parallel(
         &self,
-        reader: &mut crate::VideoReader,
+        source_path: &str,
         segments: Vec<SceneSegment>,
         width: u32,
         height: u32,
@@ -206,15 +352,11 @@ impl SceneAnalyzer {
         fps_den: u32,
         duration_ms: u64,
     ) -> Result<VaiContainer> {
-        /// Maximum raw frames to buffer before flushing a parallel encode.
-        /// At 1080p RGBA (~8 MB/frame) 500 frames ≈ 4 GB peak.
-        const CHUNK_SIZE: usize = 500;
-
         let num_segments = segments.len();
         let n_threads = num_cpus::get().max(1);
         println!(
-            "  Pass 2: encoding {} scene segment(s) in parallel ({} threads, chunk size {}) …",
-            num_segments, n_threads, CHUNK_SIZE
+            "  Pass 2: encoding {} scene segment(s) in parallel ({} threads) …",
+            num_segments, n_threads
         );
 
         let estimated_frame_count =
@@ -227,78 +369,68 @@ impl SceneAnalyzer {
 
         let quality = self.config.quality;
         let config = self.config.clone();
+        let progress = ProgressTracker::new(num_segments as u64, "Encoding segments:");
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(n_threads)
+            .build()
+            .map_err(|e| crate::Error::VideoEncode(format!("Failed to build thread pool: {e}")))?;
+
+        let segment_results: Vec<Result<SegmentResult>> = pool.install(|| {
+            segments
+                .par_iter()
+                .map(|seg| {
+                    let result = encode_segment(source_path, seg, &config, quality, ms_per_frame);
+                    progress.increment_and_report(1);
+                    result
+                })
+                .collect()
+        });
 
         let mut all_assets: Vec<Asset> = Vec::new();
         let mut all_timeline: Vec<TimelineEntry> = Vec::new();
         let mut next_asset_id: u32 = 0;
 
-        // ── Encode each segment's background up-front ──
-        println!("  Encoding {} background(s) …", num_segments);
-        for seg in &segments {
-            let bg_data = avif_encoder::encode_avif(&seg.background, quality)?;
-            all_assets.push(Asset::new(next_asset_id, width, height, bg_data));
+        for (seg, result) in segments.iter().zip(segment_results) {
+            let result = result?;
+
+            all_assets.push(Asset::new(
+                next_asset_id,
+                width,
+                height,
+                result.background_avif,
+            ));
 
             let scene_start_ms = (seg.start_frame as f64 * ms_per_frame) as u64;
-            let scene_end_ms = if seg.end_frame == usize::MAX {
-                duration_ms
-            } else {
-                (seg.end_frame as f64 * ms_per_frame) as u64
-            };
+            let scene_end_ms = (seg.end_frame as f64 * ms_per_frame) as u64;
             all_timeline.push(TimelineEntry::new(
-                next_asset_id, scene_start_ms, scene_end_ms, 0, 0, 0,
+                next_asset_id,
+                scene_start_ms,
+                scene_end_ms,
+                0,
+                0,
+                0,
             ));
             next_asset_id += 1;
-        }
 
-        // ── Stream frames, encoding in fixed-size chunks ──
-        // Buffer: (global_frame_idx, segment_index, raw RGBA image)
-        let mut chunk: Vec<(usize, usize, RgbaImage)> = Vec::with_capacity(CHUNK_SIZE);
-        let progress = ProgressTracker::new(estimated_frame_count, "Processing frames:");
-
-        reader.read_frames_streaming(|frame_idx, frame| {
-            // Find the segment this frame belongs to
-            for (seg_idx, seg) in segments.iter().enumerate() {
-                if frame_idx >= seg.start_frame && frame_idx < seg.end_frame {
-                    // First frame of each segment is the background – already encoded
-                    if frame_idx != seg.start_frame {
-                        chunk.push((frame_idx, seg_idx, frame));
-                    }
-                    break;
-                }
+            for region in result.regions {
+                all_assets.push(Asset::new(
+                    next_asset_id,
+                    region.width,
+                    region.height,
+                    region.avif_data,
+                ));
+                all_timeline.push(TimelineEntry::with_reference_mode(
+                    next_asset_id,
+                    region.start_time_ms,
+                    region.end_time_ms,
+                    region.x,
+                    region.y,
+                    1,
+                    region.mode,
+                ));
+                next_asset_id += 1;
             }
-
-            // Flush the chunk when full
-            if chunk.len() >= CHUNK_SIZE {
-                flush_chunk(
-                    &mut chunk,
-                    &segments,
-                    &config,
-                    quality,
-                    ms_per_frame,
-                    n_threads,
-                    &mut all_assets,
-                    &mut all_timeline,
-                    &mut next_asset_id,
-                )?;
-            }
-
-            progress.increment_and_report(100);
-            Ok(())
-        })?;
-
-        // Flush any remaining frames
-        if !chunk.is_empty() {
-            flush_chunk(
-                &mut chunk,
-                &segments,
-                &config,
-                quality,
-                ms_per_frame,
-                n_threads,
-                &mut all_assets,
-                &mut all_timeline,
-                &mut next_asset_id,
-            )?;
         }
 
         println!(
@@ -307,15 +439,7 @@ impl SceneAnalyzer {
             all_timeline.len()
         );
 
-        let header = VaiHeader::new(
-            width,
-            height,
-            fps_num,
-            fps_den,
-            duration_ms,
-            all_assets.len() as u32,
-            all_timeline.len() as u32,
-        );
+        let header = VaiHeader::new(width, height, fps_num, fps_den, duration_ms);
 
         Ok(VaiContainer::new(header, all_assets, all_timeline))
     }
@@ -328,92 +452,111 @@ impl SceneAnalyzer {
     ) -> Vec<(u32, u32, RgbaImage)> {
         find_diff_regions(&self.config, background, frame)
     }
+}
 
-    /// Finds the bounding box of all true values in the mask
-    fn find_bounding_box(&self, mask: &[Vec<bool>]) -> (u32, u32, u32, u32) {
-        find_bounding_box(mask)
-    }
+/// A single encoded diff region from one segment, already offset to its
+/// absolute position on the overall timeline.
+struct EncodedRegion {
+    start_time_ms: u64,
+    end_time_ms: u64,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    avif_data: Vec<u8>,
+    mode: ReferenceMode,
 }
 
-/// Encodes a chunk of buffered raw frames in parallel, appends the compact
-/// AVIF results to the output vectors, then clears the buffer to free memory.
-fn flush_chunk(
-    chunk: &mut Vec<(usize, usize, RgbaImage)>,
-    segments: &[SceneSegment],
+/// One segment's encoded output: its AVIF-encoded background plus every
+/// diff region found within it.
+struct SegmentResult {
+    background_avif: Vec<u8>,
+    regions: Vec<EncodedRegion>,
+}
+
+/// Encodes a single scene segment in isolation: opens its own `VideoReader`
+/// on `source_path` (so concurrent segments each get an independent
+/// decoder), reads only `[seg.start_frame, seg.end_frame)`, diffs every
+/// frame after the first against `seg.background` (or the running
+/// reconstruction, under `temporal_reference`), and AVIF-encodes the
+/// background and every region found via `encode_avif_auto`.
+fn encode_segment(
+    source_path: &str,
+    seg: &SceneSegment,
     config: &EncoderConfig,
     quality: u8,
     ms_per_frame: f64,
-    n_threads: usize,
-    all_assets: &mut Vec<Asset>,
-    all_timeline: &mut Vec<TimelineEntry>,
-    next_asset_id: &mut u32,
-) -> crate::Result<()> {
-    if chunk.is_empty() {
-        return Ok(());
-    }
-
-    // Each thread will produce a list of encoded regions.
-    type RegionResult = (usize, u32, u32, u32, u32, Vec<u8>); // (frame_idx, x, y, w, h, avif_data)
-
-    let per_thread = (chunk.len() + n_threads - 1) / n_threads;
-
-    let results: Vec<crate::Result<Vec<RegionResult>>> = thread::scope(|scope| {
-        let handles: Vec<_> = chunk
-            .chunks(per_thread)
-            .map(|sub| {
-                scope.spawn(move || -> crate::Result<Vec<RegionResult>> {
-                    let mut thread_results = Vec::new();
-                    for (frame_idx, seg_idx, frame) in sub {
-                        let bg = &segments[*seg_idx].background;
-                        let diff_regions = find_diff_regions(config, bg, frame);
-                        for (x, y, region_img) in diff_regions {
-                            let data = avif_encoder::encode_avif(&region_img, quality)?;
-                            thread_results.push((
-                                *frame_idx,
-                                x,
-                                y,
-                                region_img.width(),
-                                region_img.height(),
-                                data,
-                            ));
-                        }
-                    }
-                    Ok(thread_results)
-                })
-            })
-            .collect();
-
-        handles.into_iter().map(|h| h.join().unwrap()).collect()
-    });
-
-    // Collect the compact results and assign asset IDs
-    for result in results {
-        let regions = result?;
-        for (frame_idx, x, y, rw, rh, data) in regions {
-            all_assets.push(Asset::new(*next_asset_id, rw, rh, data));
+) -> crate::Result<SegmentResult> {
+    let background_avif =
+        avif_encoder::encode_avif_auto(&seg.background, quality, config.use_ffmpeg_avif)?;
+
+    let mut reader = crate::VideoReader::open(source_path)?;
+    let start_ms = (seg.start_frame as f64 * ms_per_frame) as u64;
+    let end_ms = (seg.end_frame as f64 * ms_per_frame) as u64;
+
+    let mut reconstructed = if config.temporal_reference {
+        Some(seg.background.clone())
+    } else {
+        None
+    };
+    let mut regions = Vec::new();
+
+    reader.read_frames_in_range(start_ms, end_ms, |local_idx, _pts_ms, frame| {
+        // The segment's first frame gets no regions of its own: it is shown
+        // as the segment background alone.
+        if local_idx == 0 {
+            return Ok(());
+        }
 
-            let start_time = (frame_idx as f64 * ms_per_frame) as u64;
-            let end_time = start_time + ms_per_frame as u64;
+        let mode = if config.temporal_reference {
+            ReferenceMode::Previous
+        } else {
+            ReferenceMode::Background
+        };
+        let diff_target = reconstructed.as_ref().unwrap_or(&seg.background);
+        let diff_regions = find_diff_regions(config, diff_target, &frame);
 
-            all_timeline.push(TimelineEntry::new(
-                *next_asset_id,
-                start_time,
-                end_time,
-                x as i32,
-                y as i32,
-                1,
-            ));
+        for (x, y, region_img) in diff_regions {
+            if let Some(ref mut recon) = reconstructed {
+                stamp_region(recon, &region_img, x, y);
+            }
 
-            *next_asset_id += 1;
+            let data =
+                avif_encoder::encode_avif_auto(&region_img, quality, config.use_ffmpeg_avif)?;
+            let frame_idx = seg.start_frame + local_idx;
+            let start_time_ms = (frame_idx as f64 * ms_per_frame) as u64;
+            let end_time_ms = start_time_ms + ms_per_frame as u64;
+
+            regions.push(EncodedRegion {
+                start_time_ms,
+                end_time_ms,
+                x: x as i32,
+                y: y as i32,
+                width: region_img.width(),
+                height: region_img.height(),
+                avif_data: data,
+                mode,
+            });
         }
-    }
 
-    // Free all raw frames
-    chunk.clear();
-    Ok(())
+        Ok(())
+    })?;
+
+    Ok(SegmentResult {
+        background_avif,
+        regions,
+    })
 }
 
 /// Finds regions that differ from the background (free function for use in closures)
+///
+/// Unlike a single bounding box over every changed pixel, this labels the
+/// `diff_mask` into connected components (4-connectivity) so that several
+/// independently-moving objects each get their own tight region instead of
+/// one box spanning the whole frame. Components smaller than
+/// `config.min_region_size` are dropped, and boxes separated by at most
+/// `config.merge_gap` pixels are merged into one before the final images
+/// are cropped out.
 fn find_diff_regions(
     config: &EncoderConfig,
     background: &RgbaImage,
@@ -442,50 +585,154 @@ fn find_diff_regions(
         return Vec::new();
     }
 
-    let (min_x, min_y, max_x, max_y) = find_bounding_box(&diff_mask);
-
-    let region_width = max_x - min_x + 1;
-    let region_height = max_y - min_y + 1;
+    let mut boxes = find_component_boxes(&diff_mask);
+    boxes.retain(|&(min_x, min_y, max_x, max_y)| {
+        (max_x - min_x + 1) * (max_y - min_y + 1) >= config.min_region_size
+    });
 
-    if region_width * region_height < config.min_region_size {
+    if boxes.is_empty() {
         return Vec::new();
     }
 
-    let mut region_img = ImageBuffer::new(region_width, region_height);
-    for y in 0..region_height {
-        for x in 0..region_width {
-            let src_x = min_x + x;
-            let src_y = min_y + y;
-            let pixel = frame.get_pixel(src_x, src_y);
-            region_img.put_pixel(x, y, *pixel);
-        }
-    }
+    merge_close_boxes(&mut boxes, config.merge_gap);
+
+    boxes
+        .into_iter()
+        .map(|(min_x, min_y, max_x, max_y)| {
+            let region_width = max_x - min_x + 1;
+            let region_height = max_y - min_y + 1;
+
+            let mut region_img = ImageBuffer::new(region_width, region_height);
+            for y in 0..region_height {
+                for x in 0..region_width {
+                    let src_x = min_x + x;
+                    let src_y = min_y + y;
+                    let pixel = frame.get_pixel(src_x, src_y);
+                    region_img.put_pixel(x, y, *pixel);
+                }
+            }
 
-    vec![(min_x, min_y, region_img)]
+            (min_x, min_y, region_img)
+        })
+        .collect()
 }
 
-/// Finds the bounding box of all true values in the mask
-fn find_bounding_box(mask: &[Vec<bool>]) -> (u32, u32, u32, u32) {
+/// Labels `mask` into 4-connected components via union-find and returns the
+/// `(min_x, min_y, max_x, max_y)` bounding box of each component.
+fn find_component_boxes(mask: &[Vec<bool>]) -> Vec<(u32, u32, u32, u32)> {
     let height = mask.len();
     let width = if height > 0 { mask[0].len() } else { 0 };
 
-    let mut min_x = width;
-    let mut min_y = height;
-    let mut max_x = 0;
-    let mut max_y = 0;
-
-    for (y, row) in mask.iter().enumerate() {
-        for (x, &val) in row.iter().enumerate() {
-            if val {
-                min_x = min_x.min(x);
-                min_y = min_y.min(y);
-                max_x = max_x.max(x);
-                max_y = max_y.max(y);
+    let mut parent: Vec<usize> = (0..width * height).collect();
+
+    fn find(parent: &mut [usize], x: usize) -> usize {
+        if parent[x] != x {
+            parent[x] = find(parent, parent[x]);
+        }
+        parent[x]
+    }
+
+    fn union(parent: &mut [usize], a: usize, b: usize) {
+        let ra = find(parent, a);
+        let rb = find(parent, b);
+        if ra != rb {
+            parent[ra] = rb;
+        }
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            if !mask[y][x] {
+                continue;
+            }
+            let idx = y * width + x;
+            if x > 0 && mask[y][x - 1] {
+                union(&mut parent, idx, idx - 1);
+            }
+            if y > 0 && mask[y - 1][x] {
+                union(&mut parent, idx, idx - width);
             }
         }
     }
 
-    (min_x as u32, min_y as u32, max_x as u32, max_y as u32)
+    let mut component_boxes: std::collections::BTreeMap<usize, (u32, u32, u32, u32)> =
+        std::collections::BTreeMap::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if !mask[y][x] {
+                continue;
+            }
+            let idx = y * width + x;
+            let root = find(&mut parent, idx);
+            let (x, y) = (x as u32, y as u32);
+            component_boxes
+                .entry(root)
+                .and_modify(|(min_x, min_y, max_x, max_y)| {
+                    *min_x = (*min_x).min(x);
+                    *min_y = (*min_y).min(y);
+                    *max_x = (*max_x).max(x);
+                    *max_y = (*max_y).max(y);
+                })
+                .or_insert((x, y, x, y));
+        }
+    }
+
+    // `BTreeMap` iterates by root label, which depends on mask scan order
+    // (deterministic) but not on box position; sort by position too so the
+    // returned order is a pure function of the mask, not of union-find
+    // internals.
+    let mut boxes: Vec<(u32, u32, u32, u32)> = component_boxes.into_values().collect();
+    boxes.sort_by_key(|&(min_x, min_y, max_x, max_y)| (min_y, min_x, max_y, max_x));
+    boxes
+}
+
+/// Merges bounding boxes that are within `gap` pixels of each other,
+/// repeating until no further merges occur.
+fn merge_close_boxes(boxes: &mut Vec<(u32, u32, u32, u32)>, gap: u32) {
+    loop {
+        let mut merged = false;
+
+        'outer: for i in 0..boxes.len() {
+            for j in (i + 1)..boxes.len() {
+                if boxes_within_gap(boxes[i], boxes[j], gap) {
+                    let a = boxes[i];
+                    let b = boxes.remove(j);
+                    boxes[i] = (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3));
+                    merged = true;
+                    break 'outer;
+                }
+            }
+        }
+
+        if !merged {
+            break;
+        }
+    }
+}
+
+/// Returns true if the gap between two boxes is `<= gap` pixels along both axes.
+fn boxes_within_gap(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32), gap: u32) -> bool {
+    let (a_min_x, a_min_y, a_max_x, a_max_y) = a;
+    let (b_min_x, b_min_y, b_max_x, b_max_y) = b;
+
+    let x_gap = if a_max_x < b_min_x {
+        b_min_x - a_max_x - 1
+    } else if b_max_x < a_min_x {
+        a_min_x - b_max_x - 1
+    } else {
+        0
+    };
+
+    let y_gap = if a_max_y < b_min_y {
+        b_min_y - a_max_y - 1
+    } else if b_max_y < a_min_y {
+        a_min_y - b_max_y - 1
+    } else {
+        0
+    };
+
+    x_gap <= gap && y_gap <= gap
 }
 
 /// Calculates the difference between two pixels
@@ -495,3 +742,83 @@ fn pixel_difference(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
     let db = (a[2] as i32 - b[2] as i32).abs();
     ((dr + dg + db) / 3) as u8
 }
+
+/// Downscales a frame to a `size × size` luma grid (row-major, nearest-neighbor)
+fn downscale_luma(frame: &RgbaImage, size: usize) -> Vec<u8> {
+    let src_width = frame.width().max(1);
+    let src_height = frame.height().max(1);
+
+    let mut grid = Vec::with_capacity(size * size);
+    for ty in 0..size {
+        let sy = (ty as u64 * src_height as u64 / size as u64) as u32;
+        for tx in 0..size {
+            let sx = (tx as u64 * src_width as u64 / size as u64) as u32;
+            grid.push(rgb_to_luma(frame.get_pixel(sx, sy)));
+        }
+    }
+    grid
+}
+
+/// Converts an RGBA pixel to 8-bit luma via the standard BT.601 weights
+fn rgb_to_luma(pixel: &Rgba<u8>) -> u8 {
+    let [r, g, b, _] = pixel.0;
+    (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64).round() as u8
+}
+
+/// Sum of absolute differences between two equally-sized luma grids
+fn sum_abs_diff(prev: &[u8], cur: &[u8]) -> f64 {
+    prev.iter()
+        .zip(cur.iter())
+        .map(|(&p, &c)| (p as i32 - c as i32).unsigned_abs() as f64)
+        .sum()
+}
+
+/// Mean and (population) standard deviation of a window of recent SAD
+/// values; both are `0.0` for an empty window rather than dividing by zero,
+/// so the adaptive threshold is then `0.0` and only `SAD_ABSOLUTE_FLOOR` and
+/// `min_scene_len_frames` hold back a cut.
+fn mean_and_stddev(values: &VecDeque<f64>) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+/// True if pushing `next` onto `history` would make the last `FADE_WINDOW`
+/// luma means a monotonic run (entirely non-decreasing or non-increasing)
+/// whose total drift is at least `FADE_LUMA_DELTA` — a slow fade-to-black or
+/// fade-in, which raises too little SAD per frame to cross the hard-cut
+/// threshold.
+fn is_monotonic_fade(history: &VecDeque<f64>, next: f64) -> bool {
+    if history.len() < FADE_WINDOW - 1 {
+        return false;
+    }
+    let mut window: Vec<f64> = history.iter().copied().collect();
+    window.push(next);
+
+    let first = window[0];
+    let last = *window.last().unwrap();
+    if (last - first).abs() < FADE_LUMA_DELTA {
+        return false;
+    }
+
+    let increasing = last >= first;
+    window
+        .windows(2)
+        .all(|w| if increasing { w[1] >= w[0] } else { w[1] <= w[0] })
+}
+
+/// Copies `region`'s pixels directly onto `base` at `(x, y)`, keeping a
+/// temporal-reference buffer in sync with what the decoder will reconstruct.
+/// This is a plain copy, not an alpha blend: the region is an exact crop of
+/// the source frame, not a rendered overlay.
+fn stamp_region(base: &mut RgbaImage, region: &RgbaImage, x: u32, y: u32) {
+    for ry in 0..region.height() {
+        for rx in 0..region.width() {
+            base.put_pixel(x + rx, y + ry, *region.get_pixel(rx, ry));
+        }
+    }
+}
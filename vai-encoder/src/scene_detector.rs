@@ -1,18 +1,40 @@
 //! Scene change detection for multi-pass encoding
 //!
-//! First pass: scan all frames to detect background/scene changes.
+//! First pass: scan all frames to detect scene cuts using a content-adaptive
+//! cut detector. Each frame is downscaled to a small fixed `downscale_size ×
+//! downscale_size` thumbnail and converted to HSV; the content score between
+//! consecutive frames is the mean absolute difference of H, S, and V over
+//! that thumbnail. A cut is flagged when the current score exceeds the
+//! larger of a configurable `static_threshold` (a floor below which nothing
+//! is ever considered a cut, regardless of how flat the recent history is)
+//! and `adaptive_factor` times the rolling mean of the last `window` scores,
+//! so gradual lighting changes raise the bar instead of accumulating into a
+//! false cut. `min_scene_len_frames` additionally suppresses any cut until
+//! enough frames have elapsed since the last one, preventing flicker-induced
+//! over-segmentation.
+//!
+//! Second pass: seek back to the first frame of each detected segment and use
+//! it directly as the background, since it's the frame closest to the cut and
+//! least likely to contain motion blur from whatever triggered it.
+//!
 //! This produces a list of `SceneSegment`s, each with a background frame
-//! and a time range. The segments can then be encoded in parallel.
+//! and a time range, with the trailing segment's `end_frame` already clamped
+//! to the real total frame count (there's no sentinel left for callers to
+//! handle). See `scene_analyzer::SceneAnalyzer::analyze_parallel` for the
+//! pass that encodes these segments in parallel.
 
 use crate::{Result, VideoReader};
 use image::{Rgba, RgbaImage};
+use std::collections::VecDeque;
 
 /// A detected scene segment with its background and frame range
 #[derive(Debug, Clone)]
 pub struct SceneSegment {
     /// Index of the first frame in this scene
     pub start_frame: usize,
-    /// Index one past the last frame in this scene (exclusive)
+    /// Index one past the last frame in this scene (exclusive). The trailing
+    /// segment is clamped to the real total frame count by `detect_scenes`,
+    /// so this is never a sentinel value.
     pub end_frame: usize,
     /// The background image for this scene
     pub background: RgbaImage,
@@ -28,113 +50,245 @@ impl SceneSegment {
 /// Configuration for scene detection
 #[derive(Debug, Clone)]
 pub struct SceneDetectorConfig {
-    /// Per-pixel difference threshold (0-255) used to decide if a pixel changed
-    pub pixel_threshold: u8,
-    /// Fraction of pixels that must differ to trigger a scene change (0.0 - 1.0)
-    pub scene_change_ratio: f64,
+    /// Side length of the square HSV thumbnail frames are downscaled to
+    /// before scoring
+    pub downscale_size: u32,
+    /// A cut is flagged when the content score exceeds this multiplier
+    /// applied to the rolling mean of recent scores
+    pub adaptive_factor: f64,
+    /// Floor below which a score is never considered a cut, regardless of
+    /// `adaptive_factor * rolling_mean`; keeps a long flat/static stretch
+    /// (rolling mean near zero) from flagging on the slightest noise
+    pub static_threshold: f64,
+    /// Number of recent content scores kept for the rolling-mean threshold
+    pub window: usize,
+    /// Minimum number of frames a scene must span before another cut can be flagged
+    pub min_scene_len_frames: usize,
+    /// Maximum number of frames a scene may span before a cut is forced
+    pub max_scene_len: usize,
 }
 
 impl Default for SceneDetectorConfig {
     fn default() -> Self {
         Self {
-            pixel_threshold: 40,
-            scene_change_ratio: 0.35,
+            downscale_size: 64,
+            adaptive_factor: 3.0,
+            static_threshold: 0.08,
+            window: 20,
+            min_scene_len_frames: 8,
+            max_scene_len: 300,
         }
     }
 }
 
+/// One pixel's HSV, each component normalized to `[0, 1]` (hue as a fraction
+/// of the circle rather than degrees, so wraparound is a plain `0..1` distance)
+type Hsv = [f32; 3];
+
 /// Detects scene changes across the entire video.
 ///
-/// This is the *first pass*: it reads every frame but only keeps the background
-/// images and the frame indices where scene changes occur.
+/// This is the *first pass*: it reads every frame, keeping only a downscaled
+/// HSV thumbnail per frame to score cuts cheaply. Once cut boundaries are
+/// known, a *second pass* seeks back to each segment's first frame and uses
+/// it directly as the background.
 pub fn detect_scenes(
     reader: &mut VideoReader,
     config: &SceneDetectorConfig,
 ) -> Result<Vec<SceneSegment>> {
-    let mut segments: Vec<SceneSegment> = Vec::new();
-    let mut current_bg: Option<RgbaImage> = None;
-    let mut scene_start: usize = 0;
-
-    let pixel_threshold = config.pixel_threshold;
-    let scene_change_ratio = config.scene_change_ratio;
-
-    reader.read_frames_streaming(|frame_idx, frame| {
-        match current_bg {
-            None => {
-                // Very first frame → start first scene
-                current_bg = Some(frame);
-                scene_start = 0;
+    let size = config.downscale_size.max(1) as usize;
+    let window = config.window.max(1);
+
+    let mut boundaries: Vec<usize> = vec![0];
+    let mut prev_hsv: Option<Vec<Hsv>> = None;
+    let mut recent_scores: VecDeque<f64> = VecDeque::with_capacity(window);
+    // Frames in the current scene so far, counting the frame that opened it.
+    let mut frames_since_cut: usize = 1;
+    let mut total_frames: usize = 0;
+
+    reader.read_frames_streaming(|frame_idx, _pts_ms, frame| {
+        total_frames = frame_idx + 1;
+        let hsv = downscale_hsv(&frame, size);
+
+        if let Some(ref prev) = prev_hsv {
+            let score = hsv_content_score(prev, &hsv);
+
+            let rolling_mean = if recent_scores.is_empty() {
+                0.0
+            } else {
+                recent_scores.iter().sum::<f64>() / recent_scores.len() as f64
+            };
+
+            let forced_cut = frames_since_cut >= config.max_scene_len;
+            let adaptive_cut = !forced_cut
+                && frames_since_cut >= config.min_scene_len_frames
+                && score > config.static_threshold.max(rolling_mean * config.adaptive_factor);
+
+            if forced_cut || adaptive_cut {
+                boundaries.push(frame_idx);
+                frames_since_cut = 1;
+                recent_scores.clear();
+            } else {
+                frames_since_cut += 1;
             }
-            Some(ref bg) => {
-                let changed_ratio = compute_change_ratio(bg, &frame, pixel_threshold);
-
-                if changed_ratio >= scene_change_ratio {
-                    // Scene change detected – close the current segment
-                    segments.push(SceneSegment {
-                        start_frame: scene_start,
-                        end_frame: frame_idx,
-                        background: bg.clone(),
-                    });
-                    // Start a new scene with this frame as background
-                    current_bg = Some(frame);
-                    scene_start = frame_idx;
-                }
+
+            recent_scores.push_back(score);
+            if recent_scores.len() > window {
+                recent_scores.pop_front();
             }
         }
 
+        prev_hsv = Some(hsv);
+
         if (frame_idx + 1) % 200 == 0 {
             println!(
                 "  Scene detection: scanned {} frames, {} scenes so far",
                 frame_idx + 1,
-                segments.len() + 1
+                boundaries.len()
             );
         }
 
         Ok(())
     })?;
 
-    // Close the last segment
-    if let Some(bg) = current_bg {
-        // We don't know the exact last frame index inside the callback,
-        // so we use a sentinel that the caller will clamp.
+    if total_frames == 0 {
+        return Ok(Vec::new());
+    }
+
+    let ms_per_frame = {
+        let (fps_num, fps_den) = reader.frame_rate();
+        if fps_num > 0 {
+            1000.0 * fps_den as f64 / fps_num as f64
+        } else {
+            0.0
+        }
+    };
+
+    let mut segments = Vec::with_capacity(boundaries.len());
+    for (i, &start) in boundaries.iter().enumerate() {
+        let end = boundaries.get(i + 1).copied().unwrap_or(total_frames);
+        let background = reader.read_frame_at_ms((start as f64 * ms_per_frame) as u64)?;
+
         segments.push(SceneSegment {
-            start_frame: scene_start,
-            end_frame: usize::MAX, // will be clamped by caller
-            background: bg,
+            start_frame: start,
+            // Every boundary after this one is a real cut; the trailing
+            // segment instead runs to the true total frame count.
+            end_frame: if i + 1 < boundaries.len() {
+                end
+            } else {
+                total_frames
+            },
+            background,
         });
     }
 
     Ok(segments)
 }
 
-/// Computes the fraction of pixels that differ beyond `threshold`.
-fn compute_change_ratio(a: &RgbaImage, b: &RgbaImage, threshold: u8) -> f64 {
-    let width = a.width().min(b.width());
-    let height = a.height().min(b.height());
-    let total = (width as u64) * (height as u64);
-    if total == 0 {
-        return 0.0;
+/// Seeks back into the stream and samples up to `num_samples` frames evenly
+/// spaced across `[start_frame, end_frame)`, returning their per-pixel
+/// temporal median as the segment's background.
+///
+/// Shared with `scene_analyzer::SceneAnalyzer::detect_cuts`, whose SAD-based
+/// cut detector only finds boundaries and reuses this to sample backgrounds.
+pub(crate) fn sample_median_background(
+    reader: &mut VideoReader,
+    start_frame: usize,
+    end_frame: usize,
+    num_samples: usize,
+    ms_per_frame: f64,
+) -> Result<RgbaImage> {
+    let frame_count = end_frame.saturating_sub(start_frame).max(1);
+    let num_samples = num_samples.max(1).min(frame_count);
+
+    let mut samples = Vec::with_capacity(num_samples);
+    for i in 0..num_samples {
+        let frame_idx = start_frame + (i * frame_count) / num_samples;
+        let ms = (frame_idx as f64 * ms_per_frame) as u64;
+        samples.push(reader.read_frame_at_ms(ms)?);
+    }
+
+    if samples.len() == 1 {
+        return Ok(samples.into_iter().next().unwrap());
     }
 
-    let mut changed: u64 = 0;
+    let width = samples[0].width();
+    let height = samples[0].height();
+    let mut median = RgbaImage::new(width, height);
 
+    let mut channel_values = vec![0u8; samples.len()];
     for y in 0..height {
         for x in 0..width {
-            let pa = a.get_pixel(x, y);
-            let pb = b.get_pixel(x, y);
-            if pixel_difference(pa, pb) > threshold {
-                changed += 1;
+            let mut out = [0u8; 4];
+            for c in 0..4 {
+                for (s, sample) in samples.iter().enumerate() {
+                    channel_values[s] = sample.get_pixel(x, y)[c];
+                }
+                channel_values.sort_unstable();
+                out[c] = channel_values[channel_values.len() / 2];
             }
+            median.put_pixel(x, y, Rgba(out));
         }
     }
 
-    changed as f64 / total as f64
+    Ok(median)
+}
+
+/// Downscales a frame to a `size × size` HSV thumbnail (row-major, nearest-neighbor)
+fn downscale_hsv(frame: &RgbaImage, size: usize) -> Vec<Hsv> {
+    let src_width = frame.width().max(1);
+    let src_height = frame.height().max(1);
+
+    let mut plane = Vec::with_capacity(size * size);
+    for ty in 0..size {
+        let sy = (ty as u64 * src_height as u64 / size as u64) as u32;
+        for tx in 0..size {
+            let sx = (tx as u64 * src_width as u64 / size as u64) as u32;
+            plane.push(rgb_to_hsv(frame.get_pixel(sx, sy)));
+        }
+    }
+    plane
 }
 
-/// Average channel difference between two pixels
-fn pixel_difference(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
-    let dr = (a[0] as i32 - b[0] as i32).abs();
-    let dg = (a[1] as i32 - b[1] as i32).abs();
-    let db = (a[2] as i32 - b[2] as i32).abs();
-    ((dr + dg + db) / 3) as u8
-}
\ No newline at end of file
+/// Converts an RGBA pixel to HSV, each component normalized to `[0, 1]`
+fn rgb_to_hsv(pixel: &Rgba<u8>) -> Hsv {
+    let [r, g, b, _] = pixel.0;
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let v = max;
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        (((g - b) / delta).rem_euclid(6.0)) / 6.0
+    } else if max == g {
+        (((b - r) / delta) + 2.0) / 6.0
+    } else {
+        (((r - g) / delta) + 4.0) / 6.0
+    };
+
+    [h, s, v]
+}
+
+/// Mean absolute difference of H, S, and V between two equally-sized HSV
+/// thumbnails; hue wraps around the unit circle, so its distance is the
+/// shorter of the direct and wraparound difference.
+fn hsv_content_score(prev: &[Hsv], cur: &[Hsv]) -> f64 {
+    let mut total = 0.0f64;
+    for (p, c) in prev.iter().zip(cur.iter()) {
+        let dh = hue_diff(p[0], c[0]);
+        let ds = (p[1] - c[1]).abs();
+        let dv = (p[2] - c[2]).abs();
+        total += (dh + ds + dv) as f64 / 3.0;
+    }
+    total / prev.len().max(1) as f64
+}
+
+fn hue_diff(a: f32, b: f32) -> f32 {
+    let d = (a - b).abs();
+    d.min(1.0 - d)
+}
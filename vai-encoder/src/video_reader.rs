@@ -2,7 +2,10 @@
 
 use crate::{Error, Result};
 use ffmpeg_next as ffmpeg;
+use ffmpeg_next::ffi;
 use image::{ImageBuffer, Rgba};
+use std::io::{Read, Seek, SeekFrom};
+use std::os::raw::{c_int, c_void};
 use std::sync::Once;
 
 static FFMPEG_INIT: Once = Once::new();
@@ -14,6 +17,211 @@ fn init_ffmpeg() {
     });
 }
 
+/// Decoder threading strategy, mirroring FFmpeg's `thread_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadingMode {
+    /// Decode whole frames in parallel (higher latency, better throughput).
+    Frame,
+    /// Decode slices of a frame in parallel (lower latency).
+    Slice,
+}
+
+/// Hardware decode backend to try before falling back to software decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HwAccel {
+    /// VA-API (Linux/Intel/AMD).
+    Vaapi,
+    /// NVDEC via CUDA (NVIDIA).
+    Nvdec,
+    /// VideoToolbox (macOS).
+    VideoToolbox,
+}
+
+impl HwAccel {
+    fn av_device_type(self) -> ffi::AVHWDeviceType {
+        match self {
+            HwAccel::Vaapi => ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_VAAPI,
+            HwAccel::Nvdec => ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_CUDA,
+            HwAccel::VideoToolbox => ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_VIDEOTOOLBOX,
+        }
+    }
+
+    /// The hardware-surface pixel format the decoder should be steered
+    /// toward by `get_hw_format`, matching `av_device_type`.
+    fn av_pixel_format(self) -> ffi::AVPixelFormat {
+        match self {
+            HwAccel::Vaapi => ffi::AVPixelFormat::AV_PIX_FMT_VAAPI,
+            HwAccel::Nvdec => ffi::AVPixelFormat::AV_PIX_FMT_CUDA,
+            HwAccel::VideoToolbox => ffi::AVPixelFormat::AV_PIX_FMT_VIDEOTOOLBOX,
+        }
+    }
+}
+
+/// `AVCodecContext::get_format` callback that steers the decoder toward the
+/// hardware pixel format stashed in `ctx->opaque` by `from_input`, the
+/// standard FFmpeg hwaccel negotiation dance: without this, a decoder with
+/// `hw_device_ctx` set still picks its default software format from the
+/// offered list and never actually decodes on the device. Falls back to the
+/// first software (non-hwaccel) format offered if the desired format isn't
+/// offered, and returns `AV_PIX_FMT_NONE` only if there is none.
+unsafe extern "C" fn get_hw_format(
+    ctx: *mut ffi::AVCodecContext,
+    pix_fmts: *const ffi::AVPixelFormat,
+) -> ffi::AVPixelFormat {
+    let desired = *((*ctx).opaque as *const ffi::AVPixelFormat);
+    let mut p = pix_fmts;
+    while *p != ffi::AVPixelFormat::AV_PIX_FMT_NONE {
+        if *p == desired {
+            return desired;
+        }
+        p = p.add(1);
+    }
+    let mut p = pix_fmts;
+    while *p != ffi::AVPixelFormat::AV_PIX_FMT_NONE {
+        let desc = ffi::av_pix_fmt_desc_get(*p);
+        if !desc.is_null() && ((*desc).flags & (ffi::AV_PIX_FMT_FLAG_HWACCEL as u64)) == 0 {
+            eprintln!("VideoReader: decoder didn't offer the requested hw pixel format, falling back to software decode");
+            return *p;
+        }
+        p = p.add(1);
+    }
+    ffi::AVPixelFormat::AV_PIX_FMT_NONE
+}
+
+/// Tuning knobs for `VideoReader::open_with_options`/`from_reader_with_options`.
+#[derive(Debug, Clone)]
+pub struct VideoReaderOptions {
+    /// Number of decoder threads; `0` lets FFmpeg pick automatically.
+    pub thread_count: usize,
+    /// Frame- vs. slice-parallel decoding.
+    pub threading_mode: ThreadingMode,
+    /// Caps how many decoded frames `read_frames_streaming` will pull out of
+    /// the decoder per packet before moving on, bounding how far the decode
+    /// pipeline can run ahead of the caller. `None` drains fully each time.
+    pub max_frame_delay: Option<usize>,
+    /// Hardware device to decode on; falls back to software decode if the
+    /// device can't be created.
+    pub hw_accel: Option<HwAccel>,
+}
+
+impl Default for VideoReaderOptions {
+    fn default() -> Self {
+        Self {
+            thread_count: 0,
+            threading_mode: ThreadingMode::Frame,
+            max_frame_delay: None,
+            hw_accel: None,
+        }
+    }
+}
+
+/// Target PCM format for `VideoReader::read_audio_samples_streaming`.
+#[derive(Debug, Clone, Copy)]
+pub struct AudioResampleOptions {
+    /// Output sample rate in Hz.
+    pub sample_rate: u32,
+    /// Output channel layout, e.g. `ChannelLayout::MONO` for a single waveform.
+    pub channel_layout: ffmpeg::channel_layout::ChannelLayout,
+    /// Output sample format, e.g. `Sample::F32(Type::Packed)` for interleaved `f32` PCM.
+    pub format: ffmpeg::format::Sample,
+}
+
+impl Default for AudioResampleOptions {
+    /// Mono, 16 kHz, packed `f32` — a normalized waveform suitable for
+    /// alignment/synchronization or speech segmentation against the video timeline.
+    fn default() -> Self {
+        Self {
+            sample_rate: 16_000,
+            channel_layout: ffmpeg::channel_layout::ChannelLayout::MONO,
+            format: ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+        }
+    }
+}
+
+/// Buffer size for the custom `AVIOContext` backing `from_reader`/`from_memory`.
+const AVIO_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Object-safe alias so the boxed reader behind a custom `AVIOContext` doesn't
+/// need to be generic.
+trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// The boxed reader stashed in `AVIOContext::opaque`. FFmpeg only ever touches
+/// this through the trampoline callbacks below, from the thread that drives
+/// the decode loop.
+struct AvioState {
+    reader: Box<dyn ReadSeek>,
+}
+
+unsafe extern "C" fn read_packet_trampoline(
+    opaque: *mut c_void,
+    buf: *mut u8,
+    buf_size: c_int,
+) -> c_int {
+    let state = &mut *(opaque as *mut AvioState);
+    let out = std::slice::from_raw_parts_mut(buf, buf_size as usize);
+    match state.reader.read(out) {
+        Ok(0) => ffi::AVERROR_EOF,
+        Ok(n) => n as c_int,
+        Err(_) => ffi::AVERROR(ffi::EIO),
+    }
+}
+
+unsafe extern "C" fn seek_trampoline(opaque: *mut c_void, offset: i64, whence: c_int) -> i64 {
+    let state = &mut *(opaque as *mut AvioState);
+
+    // AVSEEK_SIZE asks for the stream length without moving the position.
+    if whence & ffi::AVSEEK_SIZE != 0 {
+        let pos = match state.reader.stream_position() {
+            Ok(p) => p,
+            Err(_) => return -1,
+        };
+        let end = match state.reader.seek(SeekFrom::End(0)) {
+            Ok(e) => e,
+            Err(_) => return -1,
+        };
+        if state.reader.seek(SeekFrom::Start(pos)).is_err() {
+            return -1;
+        }
+        return end as i64;
+    }
+
+    let seek_from = match whence & !ffi::AVSEEK_FORCE {
+        0 => SeekFrom::Start(offset as u64),  // SEEK_SET
+        1 => SeekFrom::Current(offset),       // SEEK_CUR
+        2 => SeekFrom::End(offset),           // SEEK_END
+        _ => return -1,
+    };
+
+    match state.reader.seek(seek_from) {
+        Ok(pos) => pos as i64,
+        Err(_) => -1,
+    }
+}
+
+/// Owns the custom `AVIOContext` and its read buffer for the lifetime of an
+/// `Input` opened via `from_reader`/`from_memory`. Dropped after `input` (see
+/// field order in `VideoReader`) so FFmpeg is done with the context before we
+/// free it.
+struct AvioOwned {
+    ctx: *mut ffi::AVIOContext,
+    state: *mut AvioState,
+}
+
+impl Drop for AvioOwned {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.ctx.is_null() {
+                ffi::av_free((*self.ctx).buffer as *mut c_void);
+                ffi::avio_context_free(&mut self.ctx);
+            }
+            if !self.state.is_null() {
+                drop(Box::from_raw(self.state));
+            }
+        }
+    }
+}
+
 /// Video reader that extracts frames from video files
 pub struct VideoReader {
     path: Option<String>,
@@ -21,16 +229,173 @@ pub struct VideoReader {
     video_stream_index: usize,
     decoder: ffmpeg::codec::decoder::Video,
     scaler: Option<ffmpeg::software::scaling::Context>,
+    // Pixel format `scaler` was last built to convert from; rebuilt whenever
+    // the actual decoded (or hw-transferred) frame's format no longer
+    // matches, the same lazy-rebuild shape as `VideoWriter::scaler_source_dims`.
+    scaler_source_format: Option<ffmpeg::format::Pixel>,
+    // Kept alive only for readers opened via `from_reader`/`from_memory`;
+    // `None` for path-based opens where FFmpeg manages its own I/O.
+    avio: Option<AvioOwned>,
+    max_frame_delay: Option<usize>,
+    // Holds the hw device buffer reference alive for the lifetime of the
+    // decoder context that was handed it; unused (always `None`) when no
+    // hardware device was requested or creation failed.
+    hw_device_ctx: Option<*mut ffi::AVBufferRef>,
+    // The hw pixel format `get_hw_format` was told to negotiate for, so
+    // decoded frames can be recognized as hardware surfaces needing a
+    // transfer; `None` when decoding entirely in software.
+    hw_pix_fmt: Option<ffi::AVPixelFormat>,
+    // Owns the boxed `hw_pix_fmt` copy pointed to by the decoder context's
+    // `opaque` field, read back by `get_hw_format`; freed in `Drop`.
+    hw_format_opaque: Option<*mut ffi::AVPixelFormat>,
+    // Scratch frame `av_hwframe_transfer_data` downloads a hw surface into
+    // before it's handed to the scaler, reused across frames like `rgba_frame`.
+    hw_transfer_frame: ffmpeg::frame::Video,
+    // Reusable scratch frame the scaler writes RGBA into, and the reusable
+    // output buffer that's copied from it. Both are overwritten in place on
+    // every decoded frame instead of being reallocated.
+    rgba_frame: ffmpeg::frame::Video,
+    rgba_buffer: Vec<u8>,
+    // `None` when the container has no audio stream at all. The decoder and
+    // resampler are built lazily on first use, since most callers only ever
+    // read video frames.
+    audio_stream_index: Option<usize>,
+    audio_decoder: Option<ffmpeg::codec::decoder::Audio>,
+    resampler: Option<ffmpeg::software::resampling::Context>,
+}
+
+impl Drop for VideoReader {
+    fn drop(&mut self) {
+        if let Some(mut device_ctx) = self.hw_device_ctx.take() {
+            unsafe {
+                ffi::av_buffer_unref(&mut device_ctx);
+            }
+        }
+        if let Some(opaque) = self.hw_format_opaque.take() {
+            unsafe {
+                drop(Box::from_raw(opaque));
+            }
+        }
+    }
 }
 
 impl VideoReader {
     /// Opens a video file
     pub fn open(path: &str) -> Result<Self> {
+        Self::open_with_options(path, VideoReaderOptions::default())
+    }
+
+    /// Opens a video file with explicit threading/hardware-decode options.
+    pub fn open_with_options(path: &str, options: VideoReaderOptions) -> Result<Self> {
         init_ffmpeg();
 
         let input = ffmpeg::format::input(&path)?;
+        Self::from_input(input, Some(path.to_string()), None, options)
+    }
+
+    /// Opens a video from any `Read + Seek` byte source (an in-memory buffer,
+    /// an HTTP body, an archive member, ...) by feeding FFmpeg through a
+    /// custom `AVIOContext` instead of a filename.
+    pub fn from_reader<R: Read + Seek + 'static>(reader: R) -> Result<Self> {
+        Self::from_reader_with_options(reader, VideoReaderOptions::default())
+    }
+
+    /// Like `from_reader`, with explicit threading/hardware-decode options.
+    pub fn from_reader_with_options<R: Read + Seek + 'static>(
+        reader: R,
+        options: VideoReaderOptions,
+    ) -> Result<Self> {
+        init_ffmpeg();
 
-        // Find the video stream
+        let state = Box::into_raw(Box::new(AvioState {
+            reader: Box::new(reader),
+        }));
+
+        unsafe {
+            let buffer = ffi::av_malloc(AVIO_BUFFER_SIZE) as *mut u8;
+            if buffer.is_null() {
+                drop(Box::from_raw(state));
+                return Err(Error::InvalidVideo);
+            }
+
+            let avio_ctx = ffi::avio_alloc_context(
+                buffer,
+                AVIO_BUFFER_SIZE as c_int,
+                0, // write_flag: read-only
+                state as *mut c_void,
+                Some(read_packet_trampoline),
+                None, // no write_packet
+                Some(seek_trampoline),
+            );
+            if avio_ctx.is_null() {
+                ffi::av_free(buffer as *mut c_void);
+                drop(Box::from_raw(state));
+                return Err(Error::InvalidVideo);
+            }
+
+            let fmt_ctx = ffi::avformat_alloc_context();
+            if fmt_ctx.is_null() {
+                let mut ctx = avio_ctx;
+                ffi::av_free(buffer as *mut c_void);
+                ffi::avio_context_free(&mut ctx);
+                drop(Box::from_raw(state));
+                return Err(Error::InvalidVideo);
+            }
+            (*fmt_ctx).pb = avio_ctx;
+
+            let mut fmt_ctx_ptr = fmt_ctx;
+            let ret = ffi::avformat_open_input(
+                &mut fmt_ctx_ptr,
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null_mut(),
+            );
+            if ret < 0 {
+                ffi::avformat_close_input(&mut fmt_ctx_ptr);
+                let mut ctx = avio_ctx;
+                ffi::av_free(buffer as *mut c_void);
+                ffi::avio_context_free(&mut ctx);
+                drop(Box::from_raw(state));
+                return Err(Error::Ffmpeg(ffmpeg::Error::from(ret)));
+            }
+
+            if ffi::avformat_find_stream_info(fmt_ctx_ptr, std::ptr::null_mut()) < 0 {
+                ffi::avformat_close_input(&mut fmt_ctx_ptr);
+                let mut ctx = avio_ctx;
+                ffi::av_free(buffer as *mut c_void);
+                ffi::avio_context_free(&mut ctx);
+                drop(Box::from_raw(state));
+                return Err(Error::InvalidVideo);
+            }
+
+            let input = ffmpeg::format::context::Input::wrap(fmt_ctx_ptr);
+            let avio = AvioOwned {
+                ctx: avio_ctx,
+                state,
+            };
+
+            Self::from_input(input, None, Some(avio), options)
+        }
+    }
+
+    /// Convenience wrapper around `from_reader` for a fully in-memory buffer.
+    pub fn from_memory(data: &[u8]) -> Result<Self> {
+        Self::from_reader(std::io::Cursor::new(data.to_vec()))
+    }
+
+    /// Like `from_memory`, with explicit threading/hardware-decode options.
+    pub fn from_memory_with_options(data: &[u8], options: VideoReaderOptions) -> Result<Self> {
+        Self::from_reader_with_options(std::io::Cursor::new(data.to_vec()), options)
+    }
+
+    /// Shared setup once an `Input` (path- or AVIO-backed) is available:
+    /// locate the best video stream and build its decoder.
+    fn from_input(
+        input: ffmpeg::format::context::Input,
+        path: Option<String>,
+        avio: Option<AvioOwned>,
+        options: VideoReaderOptions,
+    ) -> Result<Self> {
         let video_stream = input
             .streams()
             .best(ffmpeg::media::Type::Video)
@@ -38,21 +403,83 @@ impl VideoReader {
 
         let video_stream_index = video_stream.index();
 
-        // Create decoder
-        let context =
+        let mut context =
             ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
+
+        context.set_threading(ffmpeg::codec::threading::Config {
+            kind: match options.threading_mode {
+                ThreadingMode::Frame => ffmpeg::codec::threading::Type::Frame,
+                ThreadingMode::Slice => ffmpeg::codec::threading::Type::Slice,
+            },
+            count: options.thread_count,
+            safe: true,
+        });
+
+        let mut hw_device_ctx: Option<*mut ffi::AVBufferRef> = None;
+        let mut hw_pix_fmt: Option<ffi::AVPixelFormat> = None;
+        let mut hw_format_opaque: Option<*mut ffi::AVPixelFormat> = None;
+        if let Some(hw_accel) = options.hw_accel {
+            unsafe {
+                let mut device_ctx: *mut ffi::AVBufferRef = std::ptr::null_mut();
+                let ret = ffi::av_hwdevice_ctx_create(
+                    &mut device_ctx,
+                    hw_accel.av_device_type(),
+                    std::ptr::null(),
+                    std::ptr::null_mut(),
+                    0,
+                );
+                if ret >= 0 {
+                    (*context.as_mut_ptr()).hw_device_ctx = ffi::av_buffer_ref(device_ctx);
+                    hw_device_ctx = Some(device_ctx);
+
+                    // get_format reads the desired format back out of
+                    // `opaque`; there's no other per-instance hook on
+                    // AVCodecContext to thread it through.
+                    let desired_fmt = hw_accel.av_pixel_format();
+                    let opaque = Box::into_raw(Box::new(desired_fmt));
+                    (*context.as_mut_ptr()).opaque = opaque as *mut c_void;
+                    (*context.as_mut_ptr()).get_format = Some(get_hw_format);
+                    hw_pix_fmt = Some(desired_fmt);
+                    hw_format_opaque = Some(opaque);
+                } else {
+                    eprintln!(
+                        "VideoReader: failed to initialize {:?} hw device ({}), falling back to software decode",
+                        hw_accel, ret
+                    );
+                }
+            }
+        }
+
         let decoder = context.decoder().video()?;
 
+        let audio_stream_index = input
+            .streams()
+            .best(ffmpeg::media::Type::Audio)
+            .map(|stream| stream.index());
+
         Ok(Self {
-            path: Some(path.to_string()),
+            path,
             input,
             video_stream_index,
             decoder,
             scaler: None,
+            scaler_source_format: None,
+            avio,
+            max_frame_delay: options.max_frame_delay,
+            hw_device_ctx,
+            hw_pix_fmt,
+            hw_format_opaque,
+            hw_transfer_frame: ffmpeg::frame::Video::empty(),
+            rgba_frame: ffmpeg::frame::Video::empty(),
+            rgba_buffer: Vec::new(),
+            audio_stream_index,
+            audio_decoder: None,
+            resampler: None,
         })
     }
 
-    /// Returns the file path so the reader can be re-opened for a second pass
+    /// Returns the file path so the reader can be re-opened for a second pass.
+    /// `None` for readers opened via `from_reader`/`from_memory`.
     pub fn path(&self) -> Option<&str> {
         self.path.as_deref()
     }
@@ -74,6 +501,12 @@ impl VideoReader {
         (rate.numerator() as u32, rate.denominator() as u32)
     }
 
+    /// Returns `true` if the container has an audio stream to read via
+    /// `read_audio_samples_streaming`.
+    pub fn has_audio_stream(&self) -> bool {
+        self.audio_stream_index.is_some()
+    }
+
     /// Gets the total duration in milliseconds
     pub fn duration_ms(&self) -> u64 {
         let stream = self.input.stream(self.video_stream_index).unwrap();
@@ -91,60 +524,300 @@ impl VideoReader {
         }
     }
 
-    /// Ensures the scaler is initialized
-    fn ensure_scaler(&mut self) -> Result<()> {
-        if self.scaler.is_none() {
-            self.scaler = Some(ffmpeg::software::scaling::Context::get(
-                self.decoder.format(),
-                self.decoder.width(),
-                self.decoder.height(),
-                ffmpeg::format::Pixel::RGB24,
-                self.decoder.width(),
-                self.decoder.height(),
+    /// Ensures the audio decoder for the best audio stream is built.
+    fn ensure_audio_decoder(&mut self) -> Result<()> {
+        if self.audio_decoder.is_none() {
+            let stream_index = self.audio_stream_index.ok_or(Error::NoAudioStream)?;
+            let params = self.input.stream(stream_index).unwrap().parameters();
+            let context = ffmpeg::codec::context::Context::from_parameters(params)?;
+            self.audio_decoder = Some(context.decoder().audio()?);
+        }
+        Ok(())
+    }
+
+    /// Ensures the resampler is built to convert the audio decoder's native
+    /// format into `options`.
+    fn ensure_resampler(&mut self, options: AudioResampleOptions) -> Result<()> {
+        if self.resampler.is_none() {
+            let decoder = self.audio_decoder.as_ref().ok_or(Error::NoAudioStream)?;
+            self.resampler = Some(ffmpeg::software::resampling::Context::get(
+                decoder.format(),
+                decoder.channel_layout(),
+                decoder.rate(),
+                options.format,
+                options.channel_layout,
+                options.sample_rate,
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Ensures `scaler` converts from `source`'s actual pixel format to
+    /// `RGBA`, rebuilding it if that format has changed since the last call.
+    ///
+    /// A plain software decode's format never changes, so this only ever
+    /// builds once. With `hw_accel` configured, though, `source` is whatever
+    /// `resolve_decoded_frame` produced — a hw surface's format
+    /// (`self.decoder.format()`) isn't the pixel layout swscale can actually
+    /// read; only the *transferred* frame's format (decided by the hw
+    /// device, e.g. NV12) is, and that's only known once the first frame has
+    /// been downloaded. Building lazily from the observed frame, rather than
+    /// eagerly from `self.decoder.format()`, covers both cases uniformly.
+    fn ensure_scaler_for(
+        scaler: &mut Option<ffmpeg::software::scaling::Context>,
+        scaler_source_format: &mut Option<ffmpeg::format::Pixel>,
+        source: &ffmpeg::frame::Video,
+        width: u32,
+        height: u32,
+    ) -> Result<()> {
+        let source_format = source.format();
+        if scaler.is_none() || *scaler_source_format != Some(source_format) {
+            *scaler = Some(ffmpeg::software::scaling::Context::get(
+                source_format,
+                width,
+                height,
+                ffmpeg::format::Pixel::RGBA,
+                width,
+                height,
                 ffmpeg::software::scaling::Flags::BILINEAR,
             )?);
+            *scaler_source_format = Some(source_format);
         }
         Ok(())
     }
 
-    /// Converts a decoded video frame to an RGBA ImageBuffer
+    /// Downloads `decoded` into `hw_transfer_frame` if it's a hardware
+    /// surface frame (its pixel format matches `hw_pix_fmt`), returning the
+    /// frame that should actually be scaled. Ordinary software-decoded
+    /// frames — no `hw_accel` configured, or `get_hw_format` negotiation fell
+    /// back to software decode — are returned unchanged.
+    fn resolve_decoded_frame<'a>(
+        hw_pix_fmt: Option<ffi::AVPixelFormat>,
+        hw_transfer_frame: &'a mut ffmpeg::frame::Video,
+        decoded: &'a ffmpeg::frame::Video,
+    ) -> Result<&'a ffmpeg::frame::Video> {
+        let is_hw_frame = match hw_pix_fmt {
+            Some(fmt) => unsafe { (*decoded.as_ptr()).format == fmt as i32 },
+            None => false,
+        };
+        if !is_hw_frame {
+            return Ok(decoded);
+        }
+
+        unsafe {
+            let ret =
+                ffi::av_hwframe_transfer_data(hw_transfer_frame.as_mut_ptr(), decoded.as_ptr(), 0);
+            if ret < 0 {
+                return Err(Error::Ffmpeg(ffmpeg::Error::from(ret)));
+            }
+        }
+        Ok(hw_transfer_frame)
+    }
+
+    /// Scales a decoded frame to RGBA into the reusable `rgba_frame` scratch
+    /// frame, then copies it into `rgba_buffer`, overwriting both in place.
+    /// Row-copies only strip scaler padding; there's no per-pixel loop since
+    /// the scaler output is already laid out as RGBA.
+    fn frame_to_rgba_into(
+        scaler: &mut ffmpeg::software::scaling::Context,
+        decoded: &ffmpeg::frame::Video,
+        rgba_frame: &mut ffmpeg::frame::Video,
+        rgba_buffer: &mut Vec<u8>,
+        width: u32,
+        height: u32,
+    ) -> Result<()> {
+        scaler.run(decoded, rgba_frame)?;
+
+        let data = rgba_frame.data(0);
+        let stride = rgba_frame.stride(0);
+        let row_bytes = (width * 4) as usize;
+
+        rgba_buffer.clear();
+        rgba_buffer.reserve(row_bytes * height as usize);
+        if stride == row_bytes {
+            rgba_buffer.extend_from_slice(&data[..row_bytes * height as usize]);
+        } else {
+            for y in 0..height as usize {
+                let row_start = y * stride;
+                rgba_buffer.extend_from_slice(&data[row_start..row_start + row_bytes]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts a decoded video frame to an owned RGBA ImageBuffer. Still
+    /// allocates once per call (the returned buffer can outlive the decode
+    /// loop), but reuses the scaler's scratch frame and the row-copy pass.
+    /// Prefer `read_frames_streaming_ref` when the frame doesn't need to
+    /// outlive the callback.
     fn frame_to_rgba(
         scaler: &mut ffmpeg::software::scaling::Context,
         decoded: &ffmpeg::frame::Video,
+        rgba_frame: &mut ffmpeg::frame::Video,
+        rgba_buffer: &mut Vec<u8>,
         width: u32,
         height: u32,
     ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        let mut rgb_frame = ffmpeg::frame::Video::empty();
-        scaler.run(decoded, &mut rgb_frame)?;
+        Self::frame_to_rgba_into(scaler, decoded, rgba_frame, rgba_buffer, width, height)?;
+        ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, rgba_buffer.clone())
+            .ok_or(Error::InvalidVideo)
+    }
 
-        let rgb_data = rgb_frame.data(0);
-        let stride = rgb_frame.stride(0);
-        let mut rgba_data = Vec::with_capacity((width * height * 4) as usize);
+    /// Converts a frame's PTS to milliseconds using the stream `time_base`.
+    /// Frames with no PTS (common right after a flush) report 0.
+    fn pts_to_ms(pts: Option<i64>, time_base: ffmpeg::Rational) -> u64 {
+        match pts {
+            Some(pts) if pts >= 0 => {
+                (pts as f64 * time_base.numerator() as f64 / time_base.denominator() as f64
+                    * 1000.0) as u64
+            }
+            _ => 0,
+        }
+    }
 
-        for y in 0..height as usize {
-            let row_start = y * stride;
-            for x in 0..width as usize {
-                let offset = row_start + x * 3;
-                rgba_data.push(rgb_data[offset]);     // R
-                rgba_data.push(rgb_data[offset + 1]); // G
-                rgba_data.push(rgb_data[offset + 2]); // B
-                rgba_data.push(255);                   // A
+    /// Seeks to the keyframe at or before `ms` and flushes decoder state.
+    ///
+    /// FFmpeg seeking is keyframe-granular: this lands on the nearest
+    /// *preceding* keyframe, not the exact millisecond requested. Callers
+    /// that need frame-accurate output should decode-and-discard forward
+    /// from here (see `read_frame_at_ms`/`read_frames_in_range`).
+    pub fn seek_to_ms(&mut self, ms: u64) -> Result<()> {
+        let stream = self.input.stream(self.video_stream_index).unwrap();
+        let time_base = stream.time_base();
+        let stream_index = stream.index();
+        let target_ts = (ms as f64 / 1000.0 * time_base.denominator() as f64
+            / time_base.numerator() as f64) as i64;
+
+        let ret = unsafe {
+            ffi::av_seek_frame(
+                self.input.as_mut_ptr(),
+                stream_index as c_int,
+                target_ts,
+                ffi::AVSEEK_FLAG_BACKWARD,
+            )
+        };
+        if ret < 0 {
+            return Err(Error::Ffmpeg(ffmpeg::Error::from(ret)));
+        }
+
+        self.decoder.flush();
+        Ok(())
+    }
+
+    /// Seeks to the preceding keyframe and decodes forward until reaching the
+    /// frame whose PTS (converted to milliseconds) is `>= ms`.
+    pub fn read_frame_at_ms(&mut self, ms: u64) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.seek_to_ms(ms)?;
+
+        let width = self.decoder.width();
+        let height = self.decoder.height();
+
+        let VideoReader {
+            input,
+            video_stream_index,
+            decoder,
+            scaler,
+            scaler_source_format,
+            hw_pix_fmt,
+            hw_transfer_frame,
+            rgba_frame,
+            rgba_buffer,
+            ..
+        } = self;
+        let stream_idx = *video_stream_index;
+        let time_base = input.stream(stream_idx).unwrap().time_base();
+
+        for (stream, packet) in input.packets() {
+            if stream.index() != stream_idx {
+                continue;
+            }
+
+            decoder.send_packet(&packet)?;
+
+            let mut decoded = ffmpeg::frame::Video::empty();
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                if Self::pts_to_ms(decoded.pts(), time_base) >= ms {
+                    let source =
+                        Self::resolve_decoded_frame(*hw_pix_fmt, hw_transfer_frame, &decoded)?;
+                    Self::ensure_scaler_for(scaler, scaler_source_format, source, width, height)?;
+                    let sc = scaler.as_mut().unwrap();
+                    return Self::frame_to_rgba(sc, source, rgba_frame, rgba_buffer, width, height);
+                }
             }
         }
 
-        ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, rgba_data)
-            .ok_or(Error::InvalidVideo)
+        Err(Error::InvalidVideo)
+    }
+
+    /// Decodes the best audio stream and resamples it to `options`, calling
+    /// `callback` with each chunk's start timestamp in milliseconds and its
+    /// raw PCM bytes in `options.format`/`options.channel_layout` — decoded
+    /// chunk boundaries follow the source's native frame size, not a fixed
+    /// duration, so the caller should accumulate rather than assume a fixed
+    /// chunk length. Timestamps let the audio be correlated with video
+    /// frames from `read_frames_streaming`. Returns `Error::NoAudioStream`
+    /// if the container has no audio track.
+    pub fn read_audio_samples_streaming<F>(
+        &mut self,
+        options: AudioResampleOptions,
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(u64, &[u8]) -> Result<()>,
+    {
+        self.ensure_audio_decoder()?;
+        self.ensure_resampler(options)?;
+
+        let VideoReader {
+            input,
+            audio_stream_index,
+            audio_decoder,
+            resampler,
+            ..
+        } = self;
+        let stream_idx = audio_stream_index.ok_or(Error::NoAudioStream)?;
+        let decoder = audio_decoder.as_mut().ok_or(Error::NoAudioStream)?;
+        let resampler = resampler.as_mut().ok_or(Error::NoAudioStream)?;
+        let time_base = input.stream(stream_idx).unwrap().time_base();
+
+        let mut resampled = ffmpeg::frame::Audio::empty();
+
+        for (stream, packet) in input.packets() {
+            if stream.index() != stream_idx {
+                continue;
+            }
+
+            decoder.send_packet(&packet)?;
+
+            let mut decoded = ffmpeg::frame::Audio::empty();
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                let pts_ms = Self::pts_to_ms(decoded.pts(), time_base);
+                resampler.run(&decoded, &mut resampled)?;
+                callback(pts_ms, resampled.data(0))?;
+            }
+        }
+
+        decoder.send_eof()?;
+        let mut decoded = ffmpeg::frame::Audio::empty();
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let pts_ms = Self::pts_to_ms(decoded.pts(), time_base);
+            resampler.run(&decoded, &mut resampled)?;
+            callback(pts_ms, resampled.data(0))?;
+        }
+
+        Ok(())
     }
 
     /// Reads all frames from the video, processing each frame with the given callback.
-    /// This avoids storing all frames in memory at once.
+    /// This avoids storing all frames in memory at once. The callback receives the
+    /// frame index, the frame's presentation timestamp in milliseconds, and the image.
     pub fn read_frames_streaming<F>(&mut self, mut callback: F) -> Result<()>
     where
-        F: FnMut(usize, ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<()>,
+        F: FnMut(usize, u64, ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<()>,
     {
         let width = self.decoder.width();
         let height = self.decoder.height();
-        self.ensure_scaler()?;
 
         let mut frame_index: usize = 0;
 
@@ -156,9 +829,16 @@ impl VideoReader {
             video_stream_index,
             decoder,
             scaler,
+            scaler_source_format,
+            hw_pix_fmt,
+            hw_transfer_frame,
+            rgba_frame,
+            rgba_buffer,
+            max_frame_delay,
             ..
         } = self;
         let stream_idx = *video_stream_index;
+        let time_base = input.stream(stream_idx).unwrap().time_base();
 
         for (stream, packet) in input.packets() {
             if stream.index() != stream_idx {
@@ -167,12 +847,22 @@ impl VideoReader {
 
             decoder.send_packet(&packet)?;
 
+            let mut pulled_for_packet = 0usize;
             let mut decoded = ffmpeg::frame::Video::empty();
             while decoder.receive_frame(&mut decoded).is_ok() {
-                if let Some(ref mut sc) = scaler {
-                    let img = Self::frame_to_rgba(sc, &decoded, width, height)?;
-                    callback(frame_index, img)?;
-                    frame_index += 1;
+                let source = Self::resolve_decoded_frame(*hw_pix_fmt, hw_transfer_frame, &decoded)?;
+                Self::ensure_scaler_for(scaler, scaler_source_format, source, width, height)?;
+                let sc = scaler.as_mut().unwrap();
+                let pts_ms = Self::pts_to_ms(decoded.pts(), time_base);
+                let img = Self::frame_to_rgba(sc, source, rgba_frame, rgba_buffer, width, height)?;
+                callback(frame_index, pts_ms, img)?;
+                frame_index += 1;
+
+                // Bound how many decoded frames we drain per packet so the
+                // decoder can't run arbitrarily far ahead of the caller.
+                pulled_for_packet += 1;
+                if matches!(max_frame_delay, Some(limit) if pulled_for_packet >= *limit) {
+                    break;
                 }
             }
         }
@@ -181,9 +871,150 @@ impl VideoReader {
         decoder.send_eof()?;
         let mut decoded = ffmpeg::frame::Video::empty();
         while decoder.receive_frame(&mut decoded).is_ok() {
-            if let Some(ref mut sc) = scaler {
-                let img = Self::frame_to_rgba(sc, &decoded, width, height)?;
-                callback(frame_index, img)?;
+            let source = Self::resolve_decoded_frame(*hw_pix_fmt, hw_transfer_frame, &decoded)?;
+            Self::ensure_scaler_for(scaler, scaler_source_format, source, width, height)?;
+            let sc = scaler.as_mut().unwrap();
+            let pts_ms = Self::pts_to_ms(decoded.pts(), time_base);
+            let img = Self::frame_to_rgba(sc, source, rgba_frame, rgba_buffer, width, height)?;
+            callback(frame_index, pts_ms, img)?;
+            frame_index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Like `read_frames_streaming`, but the callback receives a borrowed
+    /// view (`&ImageBuffer<Rgba<u8>, &[u8]>`) over the reader's reusable
+    /// output buffer instead of an owned, freshly-allocated one. The view is
+    /// only valid for the duration of the callback call — it is overwritten
+    /// on the next decoded frame — so use this when the frame is processed
+    /// immediately (e.g. encoded) rather than kept around.
+    pub fn read_frames_streaming_ref<F>(&mut self, mut callback: F) -> Result<()>
+    where
+        F: FnMut(usize, u64, &ImageBuffer<Rgba<u8>, &[u8]>) -> Result<()>,
+    {
+        let width = self.decoder.width();
+        let height = self.decoder.height();
+
+        let mut frame_index: usize = 0;
+
+        let VideoReader {
+            input,
+            video_stream_index,
+            decoder,
+            scaler,
+            scaler_source_format,
+            hw_pix_fmt,
+            hw_transfer_frame,
+            rgba_frame,
+            rgba_buffer,
+            max_frame_delay,
+            ..
+        } = self;
+        let stream_idx = *video_stream_index;
+        let time_base = input.stream(stream_idx).unwrap().time_base();
+
+        for (stream, packet) in input.packets() {
+            if stream.index() != stream_idx {
+                continue;
+            }
+
+            decoder.send_packet(&packet)?;
+
+            let mut pulled_for_packet = 0usize;
+            let mut decoded = ffmpeg::frame::Video::empty();
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                let source = Self::resolve_decoded_frame(*hw_pix_fmt, hw_transfer_frame, &decoded)?;
+                Self::ensure_scaler_for(scaler, scaler_source_format, source, width, height)?;
+                let sc = scaler.as_mut().unwrap();
+                let pts_ms = Self::pts_to_ms(decoded.pts(), time_base);
+                Self::frame_to_rgba_into(sc, source, rgba_frame, rgba_buffer, width, height)?;
+                let view = ImageBuffer::<Rgba<u8>, &[u8]>::from_raw(width, height, rgba_buffer.as_slice())
+                    .ok_or(Error::InvalidVideo)?;
+                callback(frame_index, pts_ms, &view)?;
+                frame_index += 1;
+
+                pulled_for_packet += 1;
+                if matches!(max_frame_delay, Some(limit) if pulled_for_packet >= *limit) {
+                    break;
+                }
+            }
+        }
+
+        decoder.send_eof()?;
+        let mut decoded = ffmpeg::frame::Video::empty();
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let source = Self::resolve_decoded_frame(*hw_pix_fmt, hw_transfer_frame, &decoded)?;
+            Self::ensure_scaler_for(scaler, scaler_source_format, source, width, height)?;
+            let sc = scaler.as_mut().unwrap();
+            let pts_ms = Self::pts_to_ms(decoded.pts(), time_base);
+            Self::frame_to_rgba_into(sc, source, rgba_frame, rgba_buffer, width, height)?;
+            let view = ImageBuffer::<Rgba<u8>, &[u8]>::from_raw(width, height, rgba_buffer.as_slice())
+                .ok_or(Error::InvalidVideo)?;
+            callback(frame_index, pts_ms, &view)?;
+            frame_index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Seeks to the window start and decodes only the frames whose PTS falls
+    /// in `[start_ms, end_ms)`, stopping as soon as the window is exhausted
+    /// instead of decoding the whole file. Frames decoded between the
+    /// preceding keyframe and `start_ms` are discarded, so the first frame
+    /// delivered is the first one whose PTS is at or after `start_ms`.
+    pub fn read_frames_in_range<F>(
+        &mut self,
+        start_ms: u64,
+        end_ms: u64,
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(usize, u64, ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<()>,
+    {
+        self.seek_to_ms(start_ms)?;
+
+        let width = self.decoder.width();
+        let height = self.decoder.height();
+        let mut frame_index: usize = 0;
+
+        let VideoReader {
+            input,
+            video_stream_index,
+            decoder,
+            scaler,
+            scaler_source_format,
+            hw_pix_fmt,
+            hw_transfer_frame,
+            rgba_frame,
+            rgba_buffer,
+            ..
+        } = self;
+        let stream_idx = *video_stream_index;
+        let time_base = input.stream(stream_idx).unwrap().time_base();
+
+        'outer: for (stream, packet) in input.packets() {
+            if stream.index() != stream_idx {
+                continue;
+            }
+
+            decoder.send_packet(&packet)?;
+
+            let mut decoded = ffmpeg::frame::Video::empty();
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                let pts_ms = Self::pts_to_ms(decoded.pts(), time_base);
+                if pts_ms < start_ms {
+                    continue;
+                }
+                if pts_ms >= end_ms {
+                    break 'outer;
+                }
+
+                let source = Self::resolve_decoded_frame(*hw_pix_fmt, hw_transfer_frame, &decoded)?;
+                Self::ensure_scaler_for(scaler, scaler_source_format, source, width, height)?;
+                let sc = scaler.as_mut().unwrap();
+                let img = Self::frame_to_rgba(sc, source, rgba_frame, rgba_buffer, width, height)?;
+                callback(frame_index, pts_ms, img)?;
                 frame_index += 1;
             }
         }
@@ -196,7 +1027,7 @@ impl VideoReader {
     /// Prefer `read_frames_streaming` for large files.
     pub fn read_frames(&mut self) -> Result<Vec<ImageBuffer<Rgba<u8>, Vec<u8>>>> {
         let mut frames = Vec::new();
-        self.read_frames_streaming(|_idx, frame| {
+        self.read_frames_streaming(|_idx, _pts_ms, frame| {
             frames.push(frame);
             Ok(())
         })?;
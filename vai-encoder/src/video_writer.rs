@@ -0,0 +1,288 @@
+//! Video writing and muxing using FFmpeg
+//!
+//! Pairs with `VideoReader` so a full read → process → write pipeline is
+//! possible in-crate: decode frames with `VideoReader`, edit the RGBA pixels
+//! in between, then mux them back into an H.264/H.265/VP9 file with
+//! `VideoWriter`.
+
+use crate::progress_tracker::ProgressTracker;
+use crate::{Error, Result, VideoReader};
+use ffmpeg_next as ffmpeg;
+use image::{ImageBuffer, Rgba};
+
+/// Output video codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoCodec {
+    H264,
+    H265,
+    Vp9,
+    Av1,
+}
+
+impl VideoCodec {
+    pub(crate) fn encoder_name(self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "libx264",
+            VideoCodec::H265 => "libx265",
+            VideoCodec::Vp9 => "libvpx-vp9",
+            VideoCodec::Av1 => "libsvtav1",
+        }
+    }
+}
+
+/// Named output resolution with an associated default bitrate, for callers
+/// that don't want to pick dimensions/bitrate by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionPreset {
+    Hd1080,
+    Hd720,
+    Sd480,
+}
+
+impl ResolutionPreset {
+    fn dimensions(self) -> (u32, u32) {
+        match self {
+            ResolutionPreset::Hd1080 => (1920, 1080),
+            ResolutionPreset::Hd720 => (1280, 720),
+            ResolutionPreset::Sd480 => (854, 480),
+        }
+    }
+
+    /// Default bitrate in bits/sec for this preset.
+    fn default_bitrate(self) -> usize {
+        match self {
+            ResolutionPreset::Hd1080 => 8_000_000,
+            ResolutionPreset::Hd720 => 5_000_000,
+            ResolutionPreset::Sd480 => 2_500_000,
+        }
+    }
+}
+
+/// Either a named resolution/bitrate preset, or an explicit target.
+#[derive(Debug, Clone, Copy)]
+pub enum OutputQuality {
+    Preset(ResolutionPreset),
+    Explicit {
+        width: u32,
+        height: u32,
+        bitrate: usize,
+    },
+}
+
+impl OutputQuality {
+    fn resolve(self) -> (u32, u32, usize) {
+        match self {
+            OutputQuality::Preset(preset) => {
+                let (width, height) = preset.dimensions();
+                (width, height, preset.default_bitrate())
+            }
+            OutputQuality::Explicit {
+                width,
+                height,
+                bitrate,
+            } => (width, height, bitrate),
+        }
+    }
+}
+
+/// Configuration for `VideoWriter::create`.
+#[derive(Debug, Clone, Copy)]
+pub struct VideoWriterOptions {
+    pub codec: VideoCodec,
+    pub fps_num: i32,
+    pub fps_den: i32,
+    pub quality: OutputQuality,
+    /// Total number of frames the caller expects to write, used only to
+    /// drive the `ProgressTracker` ETA. `None` disables progress reporting.
+    pub total_frames: Option<u64>,
+}
+
+impl Default for VideoWriterOptions {
+    fn default() -> Self {
+        Self {
+            codec: VideoCodec::H264,
+            fps_num: 30,
+            fps_den: 1,
+            quality: OutputQuality::Preset(ResolutionPreset::Hd720),
+            total_frames: None,
+        }
+    }
+}
+
+/// Writes RGBA frames to a video file, muxing them with FFmpeg.
+pub struct VideoWriter {
+    octx: ffmpeg::format::context::Output,
+    encoder: ffmpeg::codec::encoder::video::Video,
+    /// Scales incoming RGBA frames to `(width, height)` YUV420P; rebuilt in
+    /// `write_frame` whenever the incoming frame's dimensions change, since a
+    /// transcode-down/up source can differ from the output resolution.
+    scaler: Option<ffmpeg::software::scaling::Context>,
+    /// Source dimensions the current `scaler` was built for
+    scaler_source_dims: (u32, u32),
+    rgba_frame: ffmpeg::frame::Video,
+    yuv_frame: ffmpeg::frame::Video,
+    stream_index: usize,
+    time_base: ffmpeg::Rational,
+    width: u32,
+    height: u32,
+    frame_count: i64,
+    progress: Option<ProgressTracker>,
+}
+
+impl VideoWriter {
+    /// Creates a video file at `path` ready to receive frames via `write_frame`.
+    pub fn create(path: &str, options: VideoWriterOptions) -> Result<Self> {
+        ffmpeg::init()?;
+
+        let (width, height, bitrate) = options.quality.resolve();
+        // Most encoders (x264/x265/vpx) require even dimensions for 4:2:0 chroma.
+        let width = (width + 1) & !1;
+        let height = (height + 1) & !1;
+
+        let mut octx = ffmpeg::format::output(&path)?;
+        let codec = ffmpeg::encoder::find_by_name(options.codec.encoder_name()).ok_or_else(|| {
+            Error::VideoEncode(format!(
+                "No encoder found for {}",
+                options.codec.encoder_name()
+            ))
+        })?;
+
+        let mut stream = octx.add_stream(codec)?;
+        let stream_index = stream.index();
+        let time_base = ffmpeg::Rational(options.fps_den, options.fps_num);
+
+        let context =
+            ffmpeg::codec::context::Context::from_parameters(ffmpeg::codec::Parameters::new())?;
+        let mut video = context.encoder().video()?;
+        video.set_width(width);
+        video.set_height(height);
+        video.set_format(ffmpeg::format::Pixel::YUV420P);
+        video.set_time_base(time_base);
+        video.set_frame_rate(Some(ffmpeg::Rational(options.fps_num, options.fps_den)));
+        video.set_bit_rate(bitrate);
+
+        if octx
+            .format()
+            .flags()
+            .contains(ffmpeg::format::Flags::GLOBAL_HEADER)
+        {
+            video.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+
+        let encoder = video.open_as(codec)?;
+        stream.set_parameters(&encoder);
+        stream.set_time_base(time_base);
+
+        octx.write_header()?;
+
+        let progress = options
+            .total_frames
+            .map(|total| ProgressTracker::new(total, "Writing frames:"));
+
+        Ok(Self {
+            octx,
+            encoder,
+            // Built lazily in `write_frame` once the incoming frame's actual
+            // dimensions are known; see `scaler_source_dims`.
+            scaler: None,
+            scaler_source_dims: (0, 0),
+            rgba_frame: ffmpeg::frame::Video::empty(),
+            yuv_frame: ffmpeg::frame::Video::empty(),
+            stream_index,
+            time_base,
+            width,
+            height,
+            frame_count: 0,
+            progress,
+        })
+    }
+
+    /// Width frames are scaled to before encoding.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height frames are scaled to before encoding.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Scales `image` to the output resolution and feeds it to the encoder.
+    pub fn write_frame(&mut self, image: &ImageBuffer<Rgba<u8>, &[u8]>) -> Result<()> {
+        let source_dims = (image.width(), image.height());
+        if self.scaler.is_none() || self.scaler_source_dims != source_dims {
+            self.scaler = Some(ffmpeg::software::scaling::Context::get(
+                ffmpeg::format::Pixel::RGBA,
+                source_dims.0,
+                source_dims.1,
+                ffmpeg::format::Pixel::YUV420P,
+                self.width,
+                self.height,
+                ffmpeg::software::scaling::Flags::BILINEAR,
+            )?);
+            self.scaler_source_dims = source_dims;
+        }
+
+        self.rgba_frame =
+            ffmpeg::frame::Video::new(ffmpeg::format::Pixel::RGBA, image.width(), image.height());
+        let stride = self.rgba_frame.stride(0);
+        let dst = self.rgba_frame.data_mut(0);
+        let src = image.as_raw();
+        let row_bytes = (image.width() as usize) * 4;
+        for y in 0..image.height() as usize {
+            let src_off = y * row_bytes;
+            let dst_off = y * stride;
+            dst[dst_off..dst_off + row_bytes].copy_from_slice(&src[src_off..src_off + row_bytes]);
+        }
+
+        self.scaler
+            .as_mut()
+            .unwrap()
+            .run(&self.rgba_frame, &mut self.yuv_frame)?;
+        self.yuv_frame.set_pts(Some(self.frame_count));
+        self.frame_count += 1;
+
+        self.encoder.send_frame(&self.yuv_frame)?;
+        self.receive_and_write_packets()?;
+
+        if let Some(ref progress) = self.progress {
+            progress.increment_and_report(50);
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the encoder and finalizes the output container.
+    pub fn finish(mut self) -> Result<()> {
+        self.encoder.send_eof()?;
+        self.receive_and_write_packets()?;
+        self.octx.write_trailer()?;
+        Ok(())
+    }
+
+    fn receive_and_write_packets(&mut self) -> Result<()> {
+        let mut packet = ffmpeg::Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(self.stream_index);
+            packet.rescale_ts(self.time_base, self.octx.stream(self.stream_index).unwrap().time_base());
+            packet.write_interleaved(&mut self.octx)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads every frame from `reader`, hands it to `callback` for in-place
+/// processing, and writes the (possibly edited) result to `writer`. This is
+/// the in-crate read→process→write pipeline; pass a no-op callback and a
+/// `writer` created with a smaller `ResolutionPreset` to transcode down.
+pub fn transcode<F>(reader: &mut VideoReader, writer: &mut VideoWriter, mut callback: F) -> Result<()>
+where
+    F: FnMut(usize, u64, &mut ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<()>,
+{
+    reader.read_frames_streaming(|frame_idx, pts_ms, mut frame| {
+        callback(frame_idx, pts_ms, &mut frame)?;
+        let view = ImageBuffer::<Rgba<u8>, &[u8]>::from_raw(frame.width(), frame.height(), frame.as_raw())
+            .ok_or(Error::InvalidVideo)?;
+        writer.write_frame(&view)
+    })
+}
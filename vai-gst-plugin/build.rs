@@ -0,0 +1,9 @@
+/// Build script for vai-gst-plugin
+///
+/// `gst::plugin_define!` needs a handful of version/license constants that
+/// `gst-plugin-version-helper` derives from `Cargo.toml` and the surrounding
+/// git checkout; this is the same build script every gst-plugins-rs plugin
+/// crate uses.
+fn main() {
+    gst_plugin_version_helper::info()
+}
@@ -0,0 +1,22 @@
+//! VAI GStreamer Plugin
+//!
+//! A GStreamer plugin that exposes the VAI sprite-sheet video container as a
+//! `vaidemux` element, so any GStreamer-based application can play `.vai`
+//! files the same way the VLC plugin lets VLC play them.
+
+mod vaidemux;
+
+fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    vaidemux::register(plugin)
+}
+
+gst::plugin_define!(
+    vai,
+    env!("CARGO_PKG_DESCRIPTION"),
+    plugin_init,
+    env!("CARGO_PKG_VERSION"),
+    "MIT",
+    "vai",
+    "vai-gst-plugin",
+    "https://github.com/joaconigro/vai"
+);
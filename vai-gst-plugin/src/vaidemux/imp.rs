@@ -0,0 +1,387 @@
+//! `vaidemux` element implementation
+//!
+//! Wraps `vai_core::LazyVaiContainer` and `vai_decoder::LazyFrameCompositor`
+//! the same way the VLC plugin's `Demux`/`Control` functions do, but through
+//! GStreamer's own pad/segment/state-change machinery instead of VLC's
+//! `demux_t` callbacks. The sink pad is activated in pull mode so the
+//! container's `Read + Seek` requirements are satisfied by `gst_pad_pull_range`
+//! the same way `VlcStream` satisfies them via `stream_Read`/`stream_Seek`.
+
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use once_cell::sync::Lazy;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use vai_core::LazyVaiContainer;
+use vai_decoder::LazyFrameCompositor;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "vaidemux",
+        gst::DebugColorFlags::empty(),
+        Some("VAI sprite-sheet video demuxer"),
+    )
+});
+
+/// Reads container bytes from the sink pad via `gst_pad_pull_range`, so
+/// `LazyVaiContainer` can treat whatever feeds the sink pad (typically
+/// `filesrc`) as a random-access `Read + Seek` source without the plugin
+/// buffering the whole file itself. Plays the same role `VlcStream` plays
+/// for the VLC plugin.
+struct PadReader {
+    pad: gst::Pad,
+    offset: u64,
+    size: u64,
+}
+
+impl Read for PadReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let want = buf.len().min((self.size - self.offset) as usize);
+        if want == 0 {
+            return Ok(0);
+        }
+
+        let buffer = self
+            .pad
+            .pull_range(self.offset, want as u32)
+            .map_err(|e| {
+                io::Error::new(io::ErrorKind::Other, format!("pull_range failed: {e:?}"))
+            })?;
+        let map = buffer
+            .map_readable()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "failed to map pulled buffer"))?;
+
+        buf[..map.len()].copy_from_slice(&map);
+        self.offset += map.len() as u64;
+        Ok(map.len())
+    }
+}
+
+impl Seek for PadReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.offset = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(offset) => (self.offset as i64 + offset) as u64,
+            SeekFrom::End(offset) => (self.size as i64 + offset) as u64,
+        };
+        Ok(self.offset)
+    }
+}
+
+/// Per-instance state, created once the sink pad is activated in pull mode
+/// and the container header has been parsed
+struct State {
+    compositor: LazyFrameCompositor<PadReader>,
+    fps: f64,
+    duration_ms: u64,
+    current_frame: u64,
+    width: u32,
+    height: u32,
+}
+
+/// Handle to the background thread that pushes composited frames downstream;
+/// torn down on `PausedToReady`, the same way the VLC plugin tears down
+/// `DemuxSys` in `Close`.
+struct StreamingTask {
+    stop: std::sync::Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct VaiDemux {
+    sinkpad: gst::Pad,
+    srcpad: gst::Pad,
+    state: Mutex<Option<State>>,
+    task: Mutex<Option<StreamingTask>>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for VaiDemux {
+    const NAME: &'static str = "GstVaiDemux";
+    type Type = super::VaiDemux;
+    type ParentType = gst::Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = gst::Pad::builder_with_template(&templ, Some("sink")).build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .event_function(|pad, parent, event| {
+                VaiDemux::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |demux| demux.handle_src_event(pad, event),
+                )
+            })
+            .build();
+
+        Self {
+            sinkpad,
+            srcpad,
+            state: Mutex::new(None),
+            task: Mutex::new(None),
+        }
+    }
+}
+
+impl ObjectImpl for VaiDemux {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+}
+
+impl GstObjectImpl for VaiDemux {}
+
+impl ElementImpl for VaiDemux {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "VAI Demuxer",
+                "Codec/Demuxer",
+                "Demuxes a VAI sprite-sheet video container into composited RGBA frames",
+                "joaconigro",
+            )
+        });
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let sink_caps = gst::Caps::builder("application/x-vai").build();
+            let sink_pad_template = gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &sink_caps,
+            )
+            .unwrap();
+
+            let src_caps = gst::Caps::builder("video/x-raw")
+                .field("format", "RGBA")
+                .build();
+            let src_pad_template = gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &src_caps,
+            )
+            .unwrap();
+
+            vec![sink_pad_template, src_pad_template]
+        });
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        if transition == gst::StateChange::ReadyToPaused {
+            self.open().map_err(|e| {
+                gst::error!(CAT, imp: self, "failed to open container: {e}");
+                gst::StateChangeError
+            })?;
+            self.start_task();
+        }
+
+        let success = self.parent_change_state(transition)?;
+
+        if transition == gst::StateChange::PausedToReady {
+            self.stop_task();
+            *self.state.lock().unwrap() = None;
+        }
+
+        Ok(success)
+    }
+}
+
+impl VaiDemux {
+    /// Activates the sink pad in pull mode, parses the container header
+    /// through it, and pushes initial caps/segment events on the src pad;
+    /// the pull-mode equivalent of `open_impl` in the VLC plugin.
+    fn open(&self) -> Result<(), gst::ErrorMessage> {
+        self.sinkpad
+            .activate_mode(gst::PadMode::Pull, true)
+            .map_err(|_| {
+                gst::error_msg!(gst::CoreError::Pad, ["Upstream is not pull-mode seekable"])
+            })?;
+
+        let size = self
+            .sinkpad
+            .peer()
+            .and_then(|peer| peer.query_duration::<gst::format::Bytes>())
+            .map(|d| d.value() as u64)
+            .ok_or_else(|| {
+                gst::error_msg!(gst::ResourceError::Read, ["Could not query upstream size"])
+            })?;
+
+        let reader = PadReader {
+            pad: self.sinkpad.clone(),
+            offset: 0,
+            size,
+        };
+        let container = LazyVaiContainer::open(reader).map_err(|e| {
+            gst::error_msg!(
+                gst::ResourceError::Read,
+                ["Failed to parse VAI container: {e}"]
+            )
+        })?;
+
+        let fps = container.fps();
+        let duration_ms = container.header.duration_ms;
+        let width = container.header.width;
+        let height = container.header.height;
+        let fps_num = container.header.fps_num;
+        let fps_den = container.header.fps_den.max(1);
+
+        let caps = gst::Caps::builder("video/x-raw")
+            .field("format", "RGBA")
+            .field("width", width as i32)
+            .field("height", height as i32)
+            .field(
+                "framerate",
+                gst::Fraction::new(fps_num as i32, fps_den as i32),
+            )
+            .build();
+        self.srcpad.push_event(gst::event::Caps::new(&caps));
+
+        let segment = gst::FormattedSegment::<gst::ClockTime>::new();
+        self.srcpad.push_event(gst::event::Segment::new(&segment));
+
+        *self.state.lock().unwrap() = Some(State {
+            compositor: LazyFrameCompositor::new(container),
+            fps,
+            duration_ms,
+            current_frame: 0,
+            width,
+            height,
+        });
+
+        Ok(())
+    }
+
+    /// Spawns the background thread that composites and pushes one buffer
+    /// per frame until EOS or `PausedToReady`. GStreamer elements normally
+    /// drive streaming through a `gst::Task` tied to a pad; we use a plain
+    /// thread here since `vaidemux` has no pull-mode downstream contract of
+    /// its own to honor, only push-mode `src`.
+    fn start_task(&self) {
+        let stop = std::sync::Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+        let element = self.obj().clone();
+
+        let handle = std::thread::spawn(move || {
+            let imp = element.imp();
+            loop {
+                if stop_clone.load(Ordering::Relaxed) {
+                    return;
+                }
+
+                match imp.push_next_frame() {
+                    Ok(gst::FlowSuccess::Ok) => {}
+                    Ok(_) => {}
+                    Err(gst::FlowError::Eos) => {
+                        imp.srcpad.push_event(gst::event::Eos::new());
+                        return;
+                    }
+                    Err(e) => {
+                        gst::error!(CAT, imp: imp, "streaming stopped: {e:?}");
+                        imp.srcpad.push_event(gst::event::Eos::new());
+                        return;
+                    }
+                }
+            }
+        });
+
+        *self.task.lock().unwrap() = Some(StreamingTask { stop, handle });
+    }
+
+    /// Signals the streaming thread to stop and joins it; mirrors `Close`
+    /// tearing down `DemuxSys` in the VLC plugin.
+    fn stop_task(&self) {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.stop.store(true, Ordering::Relaxed);
+            let _ = task.handle.join();
+        }
+    }
+
+    /// Composites the frame at `current_frame` and pushes it downstream,
+    /// advancing `current_frame`; the push-mode equivalent of `demux_impl`.
+    fn push_next_frame(&self) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut guard = self.state.lock().unwrap();
+        let state = guard.as_mut().ok_or(gst::FlowError::Flushing)?;
+
+        let timestamp_ms = ((state.current_frame as f64 * 1000.0) / state.fps) as u64;
+        if timestamp_ms >= state.duration_ms {
+            return Err(gst::FlowError::Eos);
+        }
+
+        let frame = state
+            .compositor
+            .render_frame(timestamp_ms)
+            .map_err(|_| gst::FlowError::Error)?;
+
+        let frame_size = (state.width * state.height * 4) as usize;
+        let mut buffer = gst::Buffer::with_size(frame_size).map_err(|_| gst::FlowError::Error)?;
+        {
+            let buffer_mut = buffer.get_mut().ok_or(gst::FlowError::Error)?;
+            buffer_mut.set_pts(gst::ClockTime::from_mseconds(timestamp_ms));
+            buffer_mut.set_duration(gst::ClockTime::from_mseconds((1000.0 / state.fps) as u64));
+
+            let mut map = buffer_mut
+                .map_writable()
+                .map_err(|_| gst::FlowError::Error)?;
+            map.copy_from_slice(frame.as_raw());
+        }
+
+        state.current_frame += 1;
+        drop(guard);
+
+        self.srcpad.push(buffer)
+    }
+
+    /// Handles a seek arriving on the src pad, reusing the container's seek
+    /// index the same way `seek_to` does for the VLC plugin.
+    fn handle_src_event(&self, pad: &gst::Pad, event: gst::Event) -> bool {
+        match event.view() {
+            gst::EventView::Seek(seek) => {
+                let (_rate, _flags, _start_type, start, _stop_type, _stop) = seek.get();
+                let target_ms = match start {
+                    gst::GenericFormattedValue::Time(Some(time)) => time.mseconds(),
+                    _ => return false,
+                };
+
+                self.seek(target_ms)
+            }
+            _ => pad.event_default(Some(&*self.obj()), event),
+        }
+    }
+
+    fn seek(&self, target_ms: u64) -> bool {
+        let mut guard = self.state.lock().unwrap();
+        let Some(state) = guard.as_mut() else {
+            return false;
+        };
+
+        let target_ms = target_ms.min(state.duration_ms.saturating_sub(1));
+        if state.compositor.seek(target_ms).is_err() {
+            return false;
+        }
+        state.current_frame = ((target_ms as f64 * state.fps) / 1000.0) as u64;
+        drop(guard);
+
+        let mut segment = gst::FormattedSegment::<gst::ClockTime>::new();
+        segment.set_start(gst::ClockTime::from_mseconds(target_ms));
+        self.srcpad.push_event(gst::event::Segment::new(&segment));
+
+        true
+    }
+}
@@ -0,0 +1,24 @@
+//! Public `vaidemux` element type and registration
+//!
+//! Mirrors what `Open`/`Close` do for the VLC plugin: lets the outside world
+//! (here, GStreamer's element factory) discover and instantiate the element
+//! without reaching into `imp`'s internals.
+
+use gst::glib;
+use gst::prelude::*;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct VaiDemux(ObjectSubclass<imp::VaiDemux>) @extends gst::Element, gst::Object;
+}
+
+/// Registers the `vaidemux` element with the given plugin
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "vaidemux",
+        gst::Rank::Primary,
+        VaiDemux::static_type(),
+    )
+}
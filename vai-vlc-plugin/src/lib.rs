@@ -4,26 +4,29 @@
 //! This plugin implements VLC's demuxer API to decode and render VAI format.
 
 mod vlc_bindings;
+mod vlc_stream;
 
 use image::RgbaImage;
-use std::io::Cursor;
 use std::os::raw::{c_int, c_void};
 use std::panic;
 use std::ptr;
-use vai_core::VaiContainer;
-use vai_decoder::FrameCompositor;
+use vai_core::LazyVaiContainer;
+use vai_decoder::LazyFrameCompositor;
 use vlc_bindings::*;
+use vlc_stream::VlcStream;
 
 /// Plugin private data
 struct DemuxSys {
-    compositor: FrameCompositor,
+    compositor: LazyFrameCompositor<VlcStream>,
     es_id: *mut es_out_id_t,
     current_frame: u64,
     fps: f64,
     duration_ms: u64,
     width: u32,
     height: u32,
-    total_frames: u64,
+    /// Set via `DEMUX_SET_PAUSE_STATE`; while true, `Demux` freezes
+    /// `current_frame` instead of advancing it
+    paused: bool,
 }
 
 /// VLC module entry point - Open function
@@ -54,48 +57,34 @@ unsafe fn open_impl(obj: *mut vlc_object_t) -> c_int {
         return VLC_EGENERIC;
     }
     
-    // Probe: Read first 4 bytes to check magic
-    let mut magic = [0u8; 4];
-    let bytes_read = stream_Read(stream, magic.as_mut_ptr() as *mut c_void, 4);
-    if bytes_read != 4 {
+    // Probe: the file must open with a `vhdr` box, i.e. a 4-byte box size
+    // (ignored here) followed by the `vhdr` FourCC tag.
+    let mut head = [0u8; 8];
+    let bytes_read = stream_Read(stream, head.as_mut_ptr() as *mut c_void, 8);
+    if bytes_read != 8 {
         return VLC_EGENERIC;
     }
-    
-    // Check if it's a VAI file
-    if magic != [b'V', b'A', b'I', 0] {
+
+    if &head[4..8] != b"vhdr" {
         return VLC_EGENERIC;
     }
     
-    // Seek back to start
+    // Seek back to start so the lazy container can read the header itself
     if stream_Seek(stream, 0) != VLC_SUCCESS {
         return VLC_EGENERIC;
     }
-    
-    // Read entire file into buffer
-    let file_size = stream_GetSize(stream);
-    if file_size == 0 || file_size > 1024 * 1024 * 1024 {
-        // Sanity check: no empty files or files > 1GB
-        return VLC_EGENERIC;
-    }
-    
-    let mut buffer = vec![0u8; file_size as usize];
-    let bytes_read = stream_Read(stream, buffer.as_mut_ptr() as *mut c_void, file_size as usize);
-    if bytes_read != file_size as isize {
-        return VLC_EGENERIC;
-    }
-    
-    // Parse VAI container
-    let container = match VaiContainer::read(Cursor::new(buffer)) {
+
+    // Parse the header, timeline, and seek index eagerly (all small); asset
+    // bytes are fetched lazily from `stream` as frames are composited, so we
+    // never have to buffer the whole file into RAM.
+    let container = match LazyVaiContainer::open(VlcStream { stream }) {
         Ok(c) => c,
         Err(e) => {
             eprintln!("VAI plugin: failed to parse container: {}", e);
             return VLC_EGENERIC;
         }
     };
-    
-    // Create frame compositor
-    let compositor = FrameCompositor::new(container.clone());
-    
+
     // Get video parameters
     let width = container.header.width;
     let height = container.header.height;
@@ -103,10 +92,10 @@ unsafe fn open_impl(obj: *mut vlc_object_t) -> c_int {
     let fps_den = container.header.fps_den;
     let duration_ms = container.header.duration_ms;
     let fps = container.fps();
-    
-    // Calculate total frames
-    let total_frames = ((duration_ms as f64 * fps) / 1000.0).ceil() as u64;
-    
+
+    // Create frame compositor
+    let compositor = LazyFrameCompositor::new(container);
+
     // Set up ES format for RGBA video
     let mut fmt: es_format_t = std::mem::zeroed();
     es_format_Init(&mut fmt, VIDEO_ES, VLC_CODEC_RGBA);
@@ -137,7 +126,7 @@ unsafe fn open_impl(obj: *mut vlc_object_t) -> c_int {
         duration_ms,
         width,
         height,
-        total_frames,
+        paused: false,
     });
     
     // Store private data in demux_t
@@ -195,15 +184,21 @@ unsafe fn demux_impl(demux: *mut demux_t) -> c_int {
     }
     
     let sys = &mut *(p_sys as *mut DemuxSys);
-    
+
+    // While paused, freeze current_frame and stop pushing data without
+    // tearing down the ES.
+    if sys.paused {
+        return VLC_DEMUXER_SUCCESS;
+    }
+
     // Calculate timestamp for current frame
     let timestamp_ms = ((sys.current_frame as f64 * 1000.0) / sys.fps) as u64;
-    
+
     // Check if we've reached the end
     if timestamp_ms >= sys.duration_ms {
         return VLC_DEMUXER_EOF;
     }
-    
+
     // Render the frame
     let frame: RgbaImage = match sys.compositor.render_frame(timestamp_ms) {
         Ok(f) => f,
@@ -306,26 +301,112 @@ pub unsafe extern "C" fn Control(
         DEMUX_SET_POSITION => {
             // Seek to position (0.0 to 1.0)
             let position = *(args as *const f64);
-            let target_frame = (position * sys.total_frames as f64) as u64;
-            sys.current_frame = target_frame.min(sys.total_frames.saturating_sub(1));
-            return VLC_SUCCESS;
+            let target_ms = (position * sys.duration_ms as f64) as u64;
+            return if seek_to(demux, sys, target_ms) {
+                VLC_SUCCESS
+            } else {
+                VLC_EGENERIC
+            };
         }
-        
+
         DEMUX_SET_TIME => {
             // Seek to time in microseconds
             let time_us = *(args as *const vlc_tick_t);
-            let time_ms = (time_us / 1000) as u64;
-            let target_frame = ((time_ms as f64 * sys.fps) / 1000.0) as u64;
-            sys.current_frame = target_frame.min(sys.total_frames.saturating_sub(1));
+            let target_ms = (time_us / 1000) as u64;
+            return if seek_to(demux, sys, target_ms) {
+                VLC_SUCCESS
+            } else {
+                VLC_EGENERIC
+            };
+        }
+
+        DEMUX_CAN_SEEK => {
+            // Every timestamp maps to a frame via the container's seek index
+            let p_can_seek = *(args as *const *mut c_int);
+            if !p_can_seek.is_null() {
+                *p_can_seek = 1;
+                return VLC_SUCCESS;
+            }
+        }
+
+        DEMUX_CAN_PAUSE => {
+            let p_can_pause = *(args as *const *mut c_int);
+            if !p_can_pause.is_null() {
+                *p_can_pause = 1;
+                return VLC_SUCCESS;
+            }
+        }
+
+        DEMUX_SET_PAUSE_STATE => {
+            // Freeze current_frame without tearing down the ES; see demux_impl
+            sys.paused = *(args as *const c_int) != 0;
             return VLC_SUCCESS;
         }
-        
+
+        DEMUX_CAN_CONTROL_PACE => {
+            // We render frames on demand rather than pacing ourselves, so let
+            // VLC's clock drive us like a regular file demux
+            let p_can_control_pace = *(args as *const *mut c_int);
+            if !p_can_control_pace.is_null() {
+                *p_can_control_pace = 1;
+                return VLC_SUCCESS;
+            }
+        }
+
+        DEMUX_GET_PTS_DELAY => {
+            let p_delay = *(args as *const *mut vlc_tick_t);
+            if !p_delay.is_null() {
+                *p_delay = vlc_tick_from_ms(DEFAULT_PTS_DELAY_MS);
+                return VLC_SUCCESS;
+            }
+        }
+
+        DEMUX_GET_FPS => {
+            let p_fps = *(args as *const *mut f64);
+            if !p_fps.is_null() {
+                *p_fps = sys.fps;
+                return VLC_SUCCESS;
+            }
+        }
+
+        DEMUX_SET_NEXT_DEMUX_TIME => {
+            // Advisory hint for when VLC wants the next pf_demux call; we
+            // always render on demand, so there's nothing to schedule.
+            return VLC_SUCCESS;
+        }
+
         _ => {}
     }
-    
+
     VLC_EGENERIC
 }
 
+/// Performs a random-access seek to `target_ms` using the container's seek
+/// index, so `Previous`-mode regions are reconstructed correctly instead of
+/// just jumping `current_frame` and leaving stale compositor state behind.
+unsafe fn seek_to(demux: *mut demux_t, sys: &mut DemuxSys, target_ms: u64) -> bool {
+    let target_ms = target_ms.min(sys.duration_ms.saturating_sub(1));
+
+    if let Err(e) = sys.compositor.seek(target_ms) {
+        eprintln!("VAI plugin: seek failed: {}", e);
+        return false;
+    }
+
+    sys.current_frame = ((target_ms as f64 * sys.fps) / 1000.0) as u64;
+
+    // Binary-search the seek index for the byte offset a streaming demuxer
+    // would resume reading from, and reposition the underlying stream there.
+    if let Some(entry) = sys.compositor.container().find_seek_index(target_ms) {
+        stream_Seek((*demux).s, entry.byte_offset);
+    }
+
+    // Reset the PCR so VLC's clock doesn't stay pinned to wherever playback
+    // was before the seek.
+    es_out_Control((*demux).out, ES_OUT_SET_PCR, vlc_tick_from_ms(target_ms));
+
+    true
+}
+
 // Module descriptor
 //
 // VLC 3.x uses a specific module descriptor format. We need to export
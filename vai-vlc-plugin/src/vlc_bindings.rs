@@ -25,6 +25,16 @@ pub const DEMUX_GET_TIME: c_int = 0x1002;
 pub const DEMUX_GET_POSITION: c_int = 0x1003;
 pub const DEMUX_SET_POSITION: c_int = 0x1004;
 pub const DEMUX_SET_TIME: c_int = 0x1005;
+pub const DEMUX_CAN_SEEK: c_int = 0x1006;
+pub const DEMUX_CAN_PAUSE: c_int = 0x1007;
+pub const DEMUX_SET_PAUSE_STATE: c_int = 0x1008;
+pub const DEMUX_CAN_CONTROL_PACE: c_int = 0x1009;
+pub const DEMUX_GET_PTS_DELAY: c_int = 0x100A;
+pub const DEMUX_GET_FPS: c_int = 0x100B;
+pub const DEMUX_SET_NEXT_DEMUX_TIME: c_int = 0x100C;
+
+/// Default PTS delay, in milliseconds, reported via `DEMUX_GET_PTS_DELAY`
+pub const DEFAULT_PTS_DELAY_MS: u64 = 300;
 
 // ES out control queries
 pub const ES_OUT_SET_PCR: c_int = 0x100;
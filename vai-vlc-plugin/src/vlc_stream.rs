@@ -0,0 +1,55 @@
+//! `Read + Seek` wrapper over VLC's `stream_t` callbacks
+//!
+//! Lets `vai_core::LazyVaiContainer` pull header/timeline/seek-index bytes
+//! and, later, individual asset bytes straight from VLC's own stream object
+//! instead of requiring the whole file to be buffered into memory first.
+
+use crate::vlc_bindings::{
+    stream_GetSize, stream_Read, stream_Seek, stream_Tell, stream_t, VLC_SUCCESS,
+};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::raw::c_void;
+
+/// A VLC `stream_t` wrapped as a standard Rust `Read + Seek` source
+pub struct VlcStream {
+    pub stream: *mut stream_t,
+}
+
+impl Read for VlcStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes_read =
+            unsafe { stream_Read(self.stream, buf.as_mut_ptr() as *mut c_void, buf.len()) };
+        if bytes_read < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "VLC stream_Read failed",
+            ));
+        }
+        Ok(bytes_read as usize)
+    }
+}
+
+impl Seek for VlcStream {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(offset) => {
+                let current = unsafe { stream_Tell(self.stream) };
+                (current as i64 + offset) as u64
+            }
+            SeekFrom::End(offset) => {
+                let size = unsafe { stream_GetSize(self.stream) };
+                (size as i64 + offset) as u64
+            }
+        };
+
+        if unsafe { stream_Seek(self.stream, target) } != VLC_SUCCESS {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "VLC stream_Seek failed",
+            ));
+        }
+
+        Ok(target)
+    }
+}